@@ -1,15 +1,29 @@
 //! Handles the creation of a copy of a Resource Pack, optimized for publishing.
 
-use std::{collections::HashSet, error::Error, fs, io, path::PathBuf};
+use std::{cmp::Ordering, collections::HashSet, error::Error, fs, io, path::PathBuf};
 
+use serde_json::{Map, Value};
 use slop_rs::Slop;
 use walkdir::WalkDir;
 
 use crate::{
-    output,
+    ignore::IgnoreList,
+    image_data::InvalidImage,
+    output::{self, DASH, RED_DASH},
     paths::{self, EXPECT_UTF8_PATH},
-    scan::{images, loc, music, sounds},
-    static_file_data::{self, IMAGE_REF_NAME, IMAGE_REF_VERSION, MUSIC_REF_NAME, SOUND_REF_NAME},
+    refs,
+    scanner::ItemStatus,
+    scan::{icon, images, loc::{self, InvalidEntry, LocFileType}, music, pack, sounds},
+    static_file_data::{
+        IMAGE_REF_NAME,
+        LOC_REF_NAME,
+        ICON_FILE_NAME,
+        MUSIC_REF_NAME,
+        PACK_FILE_NAME,
+        SOUND_REF_NAME,
+    },
+    wav::WavData,
+    xnb,
 };
 
 macro_rules! path_vec {
@@ -21,7 +35,16 @@ macro_rules! path_vec {
     };
 }
 
-pub fn build_resource_pack(orig: &PathBuf, target: &PathBuf, refs: &PathBuf)
+/// Optional behaviours of the build, set through flags.
+pub struct BuildOptions {
+    /// Leave out images identical to vanilla ones.
+    pub skip_vanilla: bool,
+
+    /// Convert WAV sound effects into XNB files.
+    pub convert_sounds: bool,
+}
+
+pub fn build_resource_pack(orig: &PathBuf, target: &PathBuf, refs: &PathBuf, options: BuildOptions)
     -> Result<(), Box<dyn Error>>
 {
     output::info("ACTION - Build Resource Pack");
@@ -30,12 +53,14 @@ pub fn build_resource_pack(orig: &PathBuf, target: &PathBuf, refs: &PathBuf)
         panic!("expected `-i` to point to an existing dir");
     }
 
+    let ignore = IgnoreList::open(orig)?;
+
     prepare_target(&target)?;
-    build_root(orig, target)?;
-    build_images(orig, target, refs)?;
-    build_loc(orig, target)?;
-    build_music(orig, target, refs)?;
-    build_sounds(orig, target, refs)?;
+    build_root(orig, target, &ignore)?;
+    build_images(orig, target, refs, &ignore, options.skip_vanilla)?;
+    build_loc(orig, target, refs, &ignore)?;
+    build_music(orig, target, refs, &ignore)?;
+    build_sounds(orig, target, refs, &ignore, options.convert_sounds)?;
 
     output::divider("Build complete");
     output::info("Consider scanning both versions of the pack");
@@ -49,7 +74,7 @@ fn prepare_target(target: &PathBuf) -> io::Result<()> {
     fs::create_dir_all(target)
 }
 
-fn build_root(orig: &PathBuf, target: &PathBuf) -> Result<(), Box<dyn Error>> {
+fn build_root(orig: &PathBuf, target: &PathBuf, ignore: &IgnoreList) -> Result<(), Box<dyn Error>> {
     output::announce("Building", "/");
 
     if paths::push(orig, "workshop.json").is_file() {
@@ -57,84 +82,402 @@ fn build_root(orig: &PathBuf, target: &PathBuf) -> Result<(), Box<dyn Error>> {
         output::warn("Remember to copy it into the new version.");
     }
 
-    let root_files: HashSet<PathBuf> = HashSet::from(["icon.png".into(), "pack.json".into()]);
-    copy_files_if(&orig, &target, false, |p| root_files.contains(p))
+    let pack_file = paths::push(orig, PACK_FILE_NAME);
+
+    if pack_file.is_file() {
+        match pack::validate_pack_file(&fs::read_to_string(pack_file)?) {
+            Ok(b) if b.is_empty() => (),
+            Ok(_) => output::warn("`pack.json` has invalid fields. Run `scan` for details."),
+            Err(e) => output::warn(&format!("`pack.json` is malformed: {e}")),
+        }
+    } else {
+        output::warn("No `pack.json` found. The game will not list the pack.");
+    }
+
+    if !icon::validate_icon(&paths::push(orig, ICON_FILE_NAME))?.is_empty() {
+        output::warn("`icon.png` is missing or invalid. Run `scan` for details.");
+    }
+
+    let root_files: HashSet<PathBuf> =
+        HashSet::from([ICON_FILE_NAME.into(), PACK_FILE_NAME.into()]);
+    copy_files_if(&orig, &target, false, ignore, |p| root_files.contains(p))
 }
 
-fn build_images(orig: &PathBuf, target: &PathBuf, refs: &PathBuf) -> Result<(), Box<dyn Error>> {
+/// Copies the valid images.
+/// Images identical to vanilla ones are only left out if `skip_vanilla` is
+/// `true`.
+fn build_images(
+    orig: &PathBuf,
+    target: &PathBuf,
+    refs: &PathBuf,
+    ignore: &IgnoreList,
+    skip_vanilla: bool,
+) -> Result<(), Box<dyn Error>> {
     output::announce("Building", "/Content/Images");
 
     let slop = Slop::open(paths::push(refs, IMAGE_REF_NAME))?;
-    static_file_data::validate_slop(&slop, IMAGE_REF_VERSION);
+    refs::check_slop(&slop, &refs::IMAGE_REF);
     let data = images::slop_into_image_data(slop);
 
     let orig = paths::push(orig, "Content/Images");
     let target = paths::push(target, "Content/Images");
 
-    copy_files_if(&orig, &target, true, |p| {
-        let path = paths::push(&orig, p);
-        let result =
-            images::validate_image(path, PathBuf::new(), &orig, &data);
-        
-        if result.is_err() {
-            return false;
+    let mut left_out = vec![];
+
+    copy_files_if(&orig, &target, true, ignore, |p| {
+        match images::validate_file(paths::push(&orig, p), &orig, &data) {
+            Ok(ItemStatus::Valid) => true,
+            Ok(ItemStatus::Invalid(InvalidImage::LoadsBadly(..))) => true,
+            Ok(ItemStatus::Invalid(InvalidImage::VanillaCopy(..))) if !skip_vanilla => true,
+            Ok(ItemStatus::Invalid(b)) => {
+                left_out.push(b.to_string());
+                false
+            }
+            Ok(ItemStatus::Ignored) => false,
+            Err(e) => {
+                left_out.push(format!("{p:?}\t: {e}"));
+                false
+            }
         }
+    })?;
 
-        result.unwrap().is_valid()
-    })
+    print_left_out(&left_out);
+    Ok(())
 }
 
-fn build_loc(orig: &PathBuf, target: &PathBuf) -> Result<(), Box<dyn Error>> {
+fn build_loc(orig: &PathBuf, target: &PathBuf, refs: &PathBuf, ignore: &IgnoreList)
+    -> Result<(), Box<dyn Error>>
+{
+    let reference = loc::open_loc_ref(paths::push(refs, LOC_REF_NAME))?;
+
     output::announce("Building", "/Content/Localization");
     let orig = paths::push(orig, "Content/Localization");
     let target = paths::push(target, "Content/Localization");
 
-    copy_files_if(&orig, &target, false, |p| if let Some(path) = p.to_str() {
-        loc::RE_LOC_FILE_NAME.is_match(path)
-    } else {
-        false
-    })
+    let mut valid_files = path_vec![];
+
+    for entry in WalkDir::new(&orig).max_depth(1) {
+        let entry = entry?;
+        let path = entry.path().to_path_buf();
+
+        if path.is_dir() {
+            continue;
+        }
+
+        if ignore.is_ignored(&path) {
+            continue;
+        }
+
+        if loc::RE_LOC_FILE_NAME.is_match(paths::file_name(&path)) {
+            valid_files.push(path);
+        }
+    }
+
+    if valid_files.is_empty() {
+        output::info(&format!("No files in `{orig:?}`."));
+        return Ok(());
+    }
+
+    fs::create_dir_all(&target)?;
+
+    for path in valid_files {
+        let file_name = paths::file_name(&path);
+        let target_path = paths::push(&target, file_name);
+
+        // SAFETY: The file names were matched against `RE_LOC_FILE_NAME`.
+        let result = match LocFileType::from_file_name(file_name).unwrap() {
+            LocFileType::Csv => filter_loc_csv(&path, &target_path, file_name, &reference),
+            LocFileType::Json => filter_loc_json(&path, &target_path, file_name, &reference),
+        };
+
+        match result {
+            Ok(r) => r.print(file_name),
+            Err(e) => output::warn(&format!("Skipped `{file_name}`: {e}")),
+        }
+    }
+
+    Ok(())
+}
+
+/// What was left out of a localization file while building it.
+struct LocFilterResult {
+    kept_count: usize,
+    comment_count: usize,
+    removed: Vec<InvalidEntry>,
+}
+
+impl LocFilterResult {
+    fn new() -> Self {
+        Self { kept_count: 0, comment_count: 0, removed: vec![] }
+    }
+
+    fn print(&self, file_name: &str) {
+        let removed_count = self.removed.len();
+        let dash = if removed_count == 0 { DASH.to_string() } else { RED_DASH.to_string() };
+
+        let title = format!(
+            "`{file_name}`: Kept {} entries, removed {} comments and {removed_count} invalid entries.",
+            self.kept_count,
+            self.comment_count,
+        );
+
+        output::titled_list(&dash, &title, &self.removed);
+    }
+}
+
+/// Copies the CSV file from `orig` to `target`, keeping only the records whose
+/// key is in the reference.
+///
+/// The file is only written once it was read in full, so a malformed file
+/// leaves nothing behind.
+fn filter_loc_csv(orig: &PathBuf, target: &PathBuf, file_name: &str, reference: &HashSet<String>)
+    -> Result<LocFilterResult, Box<dyn Error>>
+{
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(orig)?;
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(vec![]);
+    let mut result = LocFilterResult::new();
+
+    writer.write_record(reader.headers()?)?;
+
+    for record in reader.records() {
+        let record = record?;
+
+        let key = match record.get(0) {
+            Some(k) if !k.trim().is_empty() => k,
+            _ => {
+                result.removed.push(InvalidEntry::EmptyRecord(file_name.to_string()));
+                continue;
+            }
+        };
+
+        if key.starts_with('#') {
+            result.comment_count += 1;
+        } else if reference.contains(key) {
+            writer.write_record(&record)?;
+            result.kept_count += 1;
+        } else {
+            result.removed.push(InvalidEntry::BadKey(file_name.to_string(), key.to_string(), vec![]));
+        }
+    }
+
+    fs::write(target, writer.into_inner().map_err(|e| e.to_string())?)?;
+    Ok(result)
+}
+
+/// Copies the JSON file from `orig` to `target`, keeping only the keys
+/// that are in the reference.
+///
+/// Nested objects are joined into dotted keys (`ItemName.CopperShortsword`)
+/// before being compared.
+fn filter_loc_json(orig: &PathBuf, target: &PathBuf, file_name: &str, reference: &HashSet<String>)
+    -> Result<LocFilterResult, Box<dyn Error>>
+{
+    let text = fs::read_to_string(orig)?;
+    let root = match serde_json::from_str(text.trim_start_matches('\u{FEFF}'))? {
+        Value::Object(o) => o,
+        _ => return Err("expected the root of the file to be an object".into()),
+    };
+
+    let mut result = LocFilterResult::new();
+    let root = filter_json_object("", root, file_name, reference, &mut result);
+
+    fs::write(target, serde_json::to_string_pretty(&Value::Object(root))?)?;
+    Ok(result)
+}
+
+fn filter_json_object(
+    prefix: &str,
+    object: Map<String, Value>,
+    file_name: &str,
+    reference: &HashSet<String>,
+    result: &mut LocFilterResult,
+) -> Map<String, Value> {
+    let mut filtered = Map::new();
+
+    for (name, value) in object {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{prefix}.{name}") };
+
+        if name.starts_with('#') {
+            result.comment_count += 1;
+            continue;
+        }
+
+        match value {
+            Value::Object(o) => {
+                let o = filter_json_object(&key, o, file_name, reference, result);
+
+                if !o.is_empty() {
+                    filtered.insert(name, Value::Object(o));
+                }
+            }
+            Value::String(_) if reference.contains(&key) => {
+                filtered.insert(name, value);
+                result.kept_count += 1;
+            }
+            Value::String(_) => {
+                result.removed.push(InvalidEntry::BadKey(file_name.to_string(), key, vec![]));
+            }
+            _ => result.removed.push(InvalidEntry::BadValue(file_name.to_string(), key)),
+        }
+    }
+
+    filtered
 }
 
-fn build_music(orig: &PathBuf, target: &PathBuf, refs: &PathBuf) -> Result<(), Box<dyn Error>> {
+fn build_music(orig: &PathBuf, target: &PathBuf, refs: &PathBuf, ignore: &IgnoreList)
+    -> Result<(), Box<dyn Error>>
+{
     let refs = music::open_music_ref(paths::push(refs, MUSIC_REF_NAME))?;
 
     output::announce("Building", "/Content/Music");
     let orig = paths::push(orig, "Content/Music");
     let target = paths::push(target, "Content/Music");
 
-    copy_files_if(&orig, &target, false, |p| {
-        match music::validate_song(p.clone(), &refs) {
-            Ok(s) => s.is_valid(),
-            Err(_) => false,
+    let mut left_out = vec![];
+
+    copy_files_if(&orig, &target, false, ignore, |p| {
+        match music::validate_song(paths::push(&orig, p), &refs) {
+            Ok(ItemStatus::Valid) => true,
+            Ok(ItemStatus::Invalid(b)) => {
+                left_out.push(b.to_string());
+                false
+            }
+            Ok(ItemStatus::Ignored) => false,
+            Err(e) => {
+                left_out.push(format!("{p:?}\t: {e}"));
+                false
+            }
         }
-    })
+    })?;
+
+    print_left_out(&left_out);
+    Ok(())
 }
 
-fn build_sounds(orig: &PathBuf, target: &PathBuf, refs: &PathBuf) -> Result<(), Box<dyn Error>> {
+/// Copies the valid sounds.
+/// WAV files are converted into XNB ones if `convert_sounds` is `true`.
+fn build_sounds(
+    orig: &PathBuf,
+    target: &PathBuf,
+    refs: &PathBuf,
+    ignore: &IgnoreList,
+    convert_sounds: bool,
+) -> Result<(), Box<dyn Error>> {
     output::announce("Building", "/Content/Sounds");
     let orig = paths::push(orig, "Content/Sounds");
     let target = paths::push(target, "Content/Sounds");
 
     let slop = Slop::open(paths::push(refs, SOUND_REF_NAME))?;
+    refs::check_slop(&slop, &refs::SOUND_REF);
     let data = sounds::slop_into_sound_data(slop);
 
-    copy_files_if(&orig, &target, true, |p| {
-        let path = paths::push(&orig, p);
-        let result =
-            sounds::validate_sound(path, PathBuf::new(), &orig, &data);
+    let mut left_out = vec![];
 
-        match result {
-            Ok(s) => s.is_valid(),
-            Err(_) => false,
+    copy_files_if(&orig, &target, true, ignore, |p| {
+        let is_wav = p.extension().is_some_and(|e| e.eq_ignore_ascii_case("wav"));
+
+        // WAVs are reported by the conversion instead.
+        if convert_sounds && is_wav {
+            return false;
+        }
+
+        match sounds::validate_file(paths::push(&orig, p), &orig, &data) {
+            Ok(ItemStatus::Valid) => true,
+            Ok(ItemStatus::Invalid(b)) => {
+                left_out.push(b.to_string());
+                false
+            }
+            Ok(ItemStatus::Ignored) => false,
+            Err(e) => {
+                left_out.push(format!("{p:?}\t: {e}"));
+                false
+            }
         }
-    })
+    })?;
+
+    print_left_out(&left_out);
+
+    if convert_sounds {
+        convert_wav_sounds(&orig, &target, &data, ignore)?;
+    }
+
+    Ok(())
 }
 
-fn copy_files_if<F>(from: &PathBuf, to: &PathBuf, recursive: bool, should_copy: F)
+/// Converts the WAV files in `orig` whose XNB name is in the reference into
+/// XNB files in `target`.
+///
+/// WAV files that already have an XNB next to them are skipped.
+fn convert_wav_sounds(orig: &PathBuf, target: &PathBuf, data: &sounds::DataMap, ignore: &IgnoreList)
     -> Result<(), Box<dyn Error>>
+{
+    output::divider("Converting WAV sounds...");
+
+    let mut converted_count = 0;
+    let mut problems = vec![];
+
+    for entry in WalkDir::new(orig) {
+        let entry = entry?;
+        let path = entry.path().to_path_buf();
+
+        let is_wav = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("wav"));
+
+        if path.is_dir() || !is_wav || ignore.is_ignored(&path) {
+            continue;
+        }
+
+        let relative_path = path.strip_prefix(orig)?.with_extension("xnb");
+
+        if !sounds::reference_contains(data, &relative_path) {
+            continue;
+        }
+
+        if paths::push(orig, &relative_path).is_file() {
+            problems.push(format!("{relative_path:?}\t: Already exists, so the WAV was skipped."));
+            continue;
+        }
+
+        match WavData::parse(&fs::read(&path)?) {
+            Ok(wav) => {
+                let xnb_path = paths::push(target, &relative_path);
+
+                // SAFETY: The path was made by pushing onto `target`.
+                fs::create_dir_all(xnb_path.parent().unwrap())?;
+                fs::write(xnb_path, xnb::write_sound_effect(&wav))?;
+                converted_count += 1;
+            }
+            Err(e) => problems.push(format!("{:?}\t: {e}", relative_path.with_extension("wav"))),
+        }
+    }
+
+    output::info(&format!("Converted {converted_count} WAV files."));
+    output::problem_list(&problems);
+    Ok(())
+}
+
+/// Prints the files that were left out of the build, along with why.
+fn print_left_out(problems: &[String]) {
+    match problems.len().cmp(&1) {
+        Ordering::Less => (),
+        Ordering::Equal => output::titled_list(&*RED_DASH, "This file was left out:", problems),
+        Ordering::Greater => {
+            let title = format!("These {} files were left out:", problems.len());
+            output::titled_list(&*RED_DASH, &title, problems);
+        }
+    }
+}
+
+fn copy_files_if<F>(
+    from: &PathBuf,
+    to: &PathBuf,
+    recursive: bool,
+    ignore: &IgnoreList,
+    mut should_copy: F,
+) -> Result<(), Box<dyn Error>>
 where
-    F: for<'a> Fn(&'a PathBuf) -> bool,
+    F: for<'a> FnMut(&'a PathBuf) -> bool,
 {
     let walk_dir = if recursive {
         WalkDir::new(from)
@@ -148,7 +491,7 @@ where
         let entry = entry?;
         let path = entry.path().strip_prefix(from)?.to_path_buf();
 
-        if path.is_dir() {
+        if entry.file_type().is_dir() {
             continue;
         }
 
@@ -156,6 +499,10 @@ where
             continue;
         }
 
+        if ignore.is_ignored(entry.path()) {
+            continue;
+        }
+
         if should_copy(&path) {
             valid_files.push(path);
         }
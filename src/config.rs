@@ -0,0 +1,63 @@
+//! Handles the optional config file at the root of a Resource Pack.
+
+use std::path::PathBuf;
+
+use slop_rs::{error::SlopResult, Slop};
+
+use crate::{paths, static_file_data::CONFIG_FILE_NAME};
+
+/// The key of the KV that holds the path to the reference dir.
+const REFERENCE_KEY: &str = "reference";
+
+/// The key of the KV that holds the version of the game the pack targets.
+const CONFIG_GAME_VERSION_KEY: &str = "game_version";
+
+/// Settings that are stored alongside a Resource Pack, so they don't have to be
+/// passed every time a command is run.
+#[derive(Default)]
+pub struct PackConfig {
+    /// The dir with the reference files.
+    /// Relative paths are relative to the pack's root dir.
+    pub reference: Option<PathBuf>,
+
+    /// The version of the game the pack targets.
+    pub game_version: Option<String>,
+}
+
+impl PackConfig {
+    /// Opens the config file in `root_dir`, or returns [None] if there isn't one.
+    pub fn open(root_dir: &PathBuf) -> SlopResult<Option<Self>> {
+        let path = paths::push(root_dir, CONFIG_FILE_NAME);
+
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let slop = Slop::open(path)?;
+        let reference = slop.get_string(REFERENCE_KEY).map(PathBuf::from);
+        let game_version = slop.get_string(CONFIG_GAME_VERSION_KEY).cloned();
+
+        Ok(Some(Self { reference, game_version }))
+    }
+
+    /// Writes the config file into `root_dir`.
+    pub fn save(&self, root_dir: &PathBuf) -> SlopResult<()> {
+        let mut slop = Slop::new();
+
+        if let Some(reference) = &self.reference {
+            let reference = reference.to_str().expect(paths::EXPECT_UTF8_PATH);
+            slop.insert(REFERENCE_KEY.to_string(), reference)?;
+        }
+
+        if let Some(game_version) = &self.game_version {
+            slop.insert(CONFIG_GAME_VERSION_KEY.to_string(), game_version.as_str())?;
+        }
+
+        slop.save(paths::push(root_dir, CONFIG_FILE_NAME))
+    }
+
+    /// Returns the reference dir, resolved from `root_dir`.
+    pub fn reference_dir(&self, root_dir: &PathBuf) -> Option<PathBuf> {
+        self.reference.as_ref().map(|r| paths::push(root_dir, r))
+    }
+}
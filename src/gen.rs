@@ -10,28 +10,48 @@ mod music;
 mod scan_data;
 mod sounds;
 
-pub fn generate_references(extracted: &PathBuf, refs: &PathBuf) -> Result<(), Box<dyn Error>> {
+/// Optional behaviours of the generation, set through flags.
+pub struct GenOptions {
+    /// Also store a hash of each image's pixels.
+    pub pixel_hashes: bool,
+
+    /// The version of the game the assets were extracted from.
+    pub game_version: Option<String>,
+}
+
+pub fn generate_references(extracted: &PathBuf, refs: &PathBuf, options: GenOptions)
+    -> Result<(), Box<dyn Error>>
+{
     assert!(refs.is_dir(), "`-o` ({refs:?}) must point to a valid dir");
     output::info("ACTION - Generate References");
 
+    let game_version = options.game_version.as_deref();
+
+    if game_version.is_none() {
+        output::warn(
+            "No `--game-version` was passed, so `scan` won't be able to tell which \
+            version of the game the references are from.",
+        );
+    }
+
     let images_dir = paths::push(extracted, "Images");
 
     if images_dir.is_dir() {
-        images::generate_image_ref(&images_dir, refs)?;
+        images::generate_image_ref(&images_dir, refs, options.pixel_hashes, game_version)?;
     }
 
     let loc_file = paths::push(extracted, ALL_LOC_CSV_NAME);
 
     if loc_file.is_file() {
-        loc::generate_loc_ref(&loc_file, refs)?;
+        loc::generate_loc_ref(&loc_file, refs, game_version)?;
     }
 
-    music::generate_music_ref(extracted, refs)?;
+    music::generate_music_ref(extracted, refs, game_version)?;
 
     let sound_dir = paths::push(extracted, "Sounds");
 
     if sound_dir.is_dir() {
-        sounds::generate_sound_ref(&sound_dir, refs)?;
+        sounds::generate_sound_ref(&sound_dir, refs, game_version)?;
     }
 
     Ok(())
@@ -7,7 +7,12 @@ use crate::{
     image_data::ImageData,
     output,
     paths,
-    static_file_data::{IMAGE_REF_NAME, IMAGE_REF_VERSION, VERSION_KEY},
+    static_file_data::{
+        COUNT_KEY,
+        GAME_VERSION_KEY,
+        GENERATED_KEY,
+        IMAGE_REF_NAME, IMAGE_REF_VERSION, VERSION_KEY},
+    text_ref,
 };
 
 use super::scan_data::ScanData;
@@ -22,7 +27,15 @@ impl Display for InvalidImage {
     }
 }
 
-pub fn generate_image_ref(image_dir: &PathBuf, refs: &PathBuf) -> io::Result<()> {
+/// Generates the `images.slop` file.
+/// Pixel hashes are only stored if `pixel_hashes` is `true`, as they are slow
+/// to calculate.
+pub fn generate_image_ref(
+    image_dir: &PathBuf,
+    refs: &PathBuf,
+    pixel_hashes: bool,
+    game_version: Option<&str>,
+) -> io::Result<()> {
     output::divider("Generating image reference...");
 
     let mut scan_data = ScanData::new("images");
@@ -39,7 +52,7 @@ pub fn generate_image_ref(image_dir: &PathBuf, refs: &PathBuf) -> io::Result<()>
         }
 
         let parent = paths::sanitize_path(path.clone(), image_dir);
-        register_item(&mut scan_data, &parent, &path);
+        register_item(&mut scan_data, &parent, &path, pixel_hashes);
 
         let joined_count = scan_data.joined_count() as u32;
 
@@ -53,7 +66,7 @@ pub fn generate_image_ref(image_dir: &PathBuf, refs: &PathBuf) -> io::Result<()>
     output::divider("Scan complete.");
     scan_data.print_results();
 
-    let slop = generate_slop(&scan_data);
+    let slop = generate_slop(&scan_data, game_version);
     output::divider("Writing SLOP to disk...");
 
     slop
@@ -63,21 +76,26 @@ pub fn generate_image_ref(image_dir: &PathBuf, refs: &PathBuf) -> io::Result<()>
     Ok(())
 }
 
-fn register_item(data: &mut ImageScanData, parent: &PathBuf, path: &PathBuf) {
+fn register_item(data: &mut ImageScanData, parent: &PathBuf, path: &PathBuf, pixel_hashes: bool) {
     let key = paths::path_buf_to_key_name(parent);
 
-    match ImageData::open(path) {
+    match ImageData::open(path, pixel_hashes) {
         Ok(d) => data.push_valid(key, d),
         Err(_e) => data.push_invalid(key, InvalidImage(path.clone())),
     }
 }
 
-fn generate_slop(data: &ImageScanData) -> Slop {
+fn generate_slop(data: &ImageScanData, game_version: Option<&str>) -> Slop {
     output::divider("Converting to SLOP file...");
     
     let mut slop = Slop::new();
     slop.insert_unchecked(VERSION_KEY.to_string(), IMAGE_REF_VERSION.to_string());
-    slop.insert_unchecked("!count".to_string(), data.joined_count().to_string());
+    slop.insert_unchecked(COUNT_KEY.to_string(), data.joined_count().to_string());
+    slop.insert_unchecked(GENERATED_KEY.to_string(), text_ref::today());
+
+    if let Some(v) = game_version {
+        slop.insert_unchecked(GAME_VERSION_KEY.to_string(), v);
+    }
 
     for (key, images) in &data.valid_entries {
         let images: Vec<String> = images
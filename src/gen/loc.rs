@@ -1,30 +1,47 @@
-use std::{error::Error, fs, io, path::PathBuf};
+use std::{error::Error, io, path::PathBuf};
 
-use csv::Reader;
+use csv::{Reader, Writer};
 
-use crate::{output, paths, static_file_data::LOC_REF_NAME};
+use crate::{
+    loc_data::locale_of_column,
+    output,
+    paths,
+    static_file_data::{LOC_REF_NAME, LOC_REF_VERSION, LOC_VALUES_REF_NAME},
+    text_ref::TextRef,
+};
 
-pub fn generate_loc_ref(loc_path: &PathBuf, refs: &PathBuf) -> Result<(), Box<dyn Error>> {
+pub fn generate_loc_ref(loc_path: &PathBuf, refs: &PathBuf, game_version: Option<&str>)
+    -> Result<(), Box<dyn Error>>
+{
     output::announce_path("Scanning", loc_path);
 
     let mut reader = Reader::from_path(loc_path)?;
+    let mut writer = Writer::from_path(paths::push(refs, LOC_VALUES_REF_NAME))?;
     let mut keys = vec![];
-    
+
     output::divider("Generating localization reference...");
 
+    // The header row is rewritten with proper locale tags, in case `Loc.csv`
+    // names its columns differently.
+    let mut header = vec!["Key"];
+    header.extend(reader.headers()?.iter().skip(1).enumerate().map(|(i, h)| locale_of_column(h, i)));
+    writer.write_record(&header)?;
+
     for record in reader.records() {
         let record = record?;
 
         if let Some(field) = record.get(0) {
             keys.push(field.to_string());
+            writer.write_record(&record)?;
         }
     }
 
     output::update_progress(&mut io::stdout().lock(), "entries", keys.len() as u32)?;
     println!();
 
-    output::divider("Writing reference file to disk...");
-    fs::write(paths::push(refs, LOC_REF_NAME), keys.join("\n"))?;
+    output::divider("Writing reference files to disk...");
+    TextRef::new(LOC_REF_VERSION, keys, game_version).save(paths::push(refs, LOC_REF_NAME))?;
+    writer.flush()?;
 
     Ok(())
 }
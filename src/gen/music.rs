@@ -1,25 +1,105 @@
-use std::{fs, io, path::PathBuf};
+use std::{collections::HashMap, error::Error, io, path::PathBuf};
 
 use lazy_static::lazy_static;
 use regex::Regex;
 use walkdir::WalkDir;
 
-use crate::{output, paths, static_file_data::MUSIC_REF_NAME};
+use crate::{
+    output,
+    paths,
+    static_file_data::{MUSIC_REF_NAME, MUSIC_REF_VERSION, WAVE_BANK_NAME},
+    text_ref::TextRef,
+    xwb,
+};
 
-pub fn generate_music_ref(root_dir: &PathBuf, refs: &PathBuf) -> io::Result<()> {
+/// Generates the music reference from the wave bank in `root_dir` if there is
+/// one, or from the music files extracted out of it otherwise.
+///
+/// Songs the wave bank has no title for take the title of their extracted
+/// file, if there is one.
+pub fn generate_music_ref(root_dir: &PathBuf, refs: &PathBuf, game_version: Option<&str>)
+    -> Result<(), Box<dyn Error>>
+{
+    let wave_bank = paths::push(root_dir, WAVE_BANK_NAME);
+
+    let songs = if wave_bank.is_file() {
+        let mut songs = read_wave_bank(&wave_bank)?;
+
+        if songs.iter().any(|(_, t)| t.is_empty()) {
+            merge_titles(&mut songs, scan_music_files(root_dir)?);
+        }
+
+        songs
+    } else {
+        scan_music_files(root_dir)?
+    };
+
+    output::divider("Writing reference file to disk...");
+    write_music_ref(refs, songs, game_version)?;
+    Ok(())
+}
+
+/// Reads the songs straight out of the wave bank, listing the format and
+/// duration of each one.
+///
+/// Entries are numbered in the order they are stored in, starting at 1, like
+/// the game's music cues. Their titles are only known if the bank stores names.
+/// The format and duration are only listed, not stored in the reference.
+fn read_wave_bank(wave_bank: &PathBuf) -> Result<Vec<(usize, String)>, Box<dyn Error>> {
+    output::announce_path("Reading music entries in", wave_bank);
+
+    let entries = xwb::read_entries(wave_bank)?;
+
+    output::divider(&format!("Found {} songs.", entries.len()));
+    output::bullet_list(
+        output::DASH.to_string(),
+        entries.iter().enumerate().map(|(i, e)| match &e.name {
+            Some(n) => format!("Music_{} ({n})\t: {e}", i + 1),
+            None => format!("Music_{}\t: {e}", i + 1),
+        }),
+    );
+
+    let songs = entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| (i + 1, e.name.unwrap_or_default()))
+        .collect();
+
+    Ok(songs)
+}
+
+/// Gives the songs without a title the title of the extracted song with the
+/// same ID.
+fn merge_titles(songs: &mut [(usize, String)], extracted: Vec<(usize, String)>) {
+    let titles: HashMap<usize, String> = extracted
+        .into_iter()
+        .filter(|(_, t)| !t.is_empty())
+        .collect();
+
+    for (id, title) in songs.iter_mut().filter(|(_, t)| t.is_empty()) {
+        if let Some(t) = titles.get(id) {
+            title.clone_from(t);
+        }
+    }
+}
+
+/// Collects the songs from the names of the music files extracted out of the
+/// wave bank.
+fn scan_music_files(root_dir: &PathBuf) -> io::Result<Vec<(usize, String)>> {
     lazy_static! {
         /// Matches the file names of music files.
         /// 
         /// Captures:
         /// - `1`: The song's ID.
-        static ref RE_MUSIC_FILE_NAME: Regex = Regex::new(r"([0-9]{2,}).*?\.wav").unwrap();
+        /// - `2`: The song's title, possibly surrounded by separators.
+        static ref RE_MUSIC_FILE_NAME: Regex = Regex::new(r"([0-9]{2,})(.*?)\.wav").unwrap();
     }
 
     output::announce_path("Scanning music files in", root_dir);
 
     let mut stdout = io::stdout().lock();
 
-    let mut ids: Vec<usize> = vec![];
+    let mut songs: Vec<(usize, String)> = vec![];
     let mut id_count = 0;
 
     for entry in WalkDir::new(root_dir).max_depth(1) {
@@ -37,8 +117,11 @@ pub fn generate_music_ref(root_dir: &PathBuf, refs: &PathBuf) -> io::Result<()>
             None => continue,
         };
 
-        // SAFETY: The pattern includes group 1.
-        ids.push(caps[1].parse().expect("expected a valid uint str"));
+        // SAFETY: The pattern includes groups 1 and 2.
+        let id = caps[1].parse().expect("expected a valid uint str");
+        let title = caps[2].trim_matches(|c: char| c.is_whitespace() || "-_.".contains(c));
+
+        songs.push((id, title.to_string()));
         id_count += 1;
 
         if id_count % 10 == 0 {
@@ -50,15 +133,25 @@ pub fn generate_music_ref(root_dir: &PathBuf, refs: &PathBuf) -> io::Result<()>
     println!();
 
     output::divider("Scan complete.");
-    output::divider("Writing reference file to disk...");
+    Ok(songs)
+}
+
+/// Writes `songs`, sorted by ID, to the music reference.
+fn write_music_ref(refs: &PathBuf, mut songs: Vec<(usize, String)>, game_version: Option<&str>)
+    -> io::Result<()>
+{
+    songs.sort();
 
-    let ids = ids
+    let songs: Vec<String> = songs
         .iter()
-        .fold(String::new(), |mut acc, id| {
-            acc.push_str(&format!("Music_{id}\n"));
-            acc
-        });
+        .map(|(id, title)| {
+            if title.is_empty() {
+                format!("Music_{id}")
+            } else {
+                format!("Music_{id} = {title}")
+            }
+        })
+        .collect();
 
-    fs::write(paths::push(refs, MUSIC_REF_NAME), ids)?;
-    Ok(())
+    TextRef::new(MUSIC_REF_VERSION, songs, game_version).save(paths::push(refs, MUSIC_REF_NAME))
 }
@@ -1,4 +1,4 @@
-use std::{ffi::OsStr, fmt::Display, io, path::PathBuf};
+use std::{ffi::OsStr, fmt::Display, fs, io, path::PathBuf};
 
 use slop_rs::Slop;
 use walkdir::WalkDir;
@@ -6,32 +6,39 @@ use walkdir::WalkDir;
 use crate::{
     output,
     paths,
-    static_file_data::{SOUND_REF_NAME, SOUND_REF_VERSION, VERSION_KEY},
+    sound_data::{SoundData, SoundFormat},
+    static_file_data::{
+        COUNT_KEY,
+        GAME_VERSION_KEY,
+        GENERATED_KEY,
+        SOUND_REF_NAME,
+        SOUND_REF_VERSION,
+        VERSION_KEY,
+    },
+    text_ref,
+    wav::WavData,
 };
 
 use super::scan_data::ScanData;
 
 type SoundScanData = ScanData<SoundData, InvalidSound>;
 
-struct SoundData {
-    path: String,
-}
-
-impl SoundData {
-    fn open(mut path: PathBuf) -> Result<Self, InvalidSound> {
-        if path.extension() != Some(OsStr::new("wav")) {
-            return Err(InvalidSound(path));
-        }
-
-        path.set_extension("xnb");
-        Ok(Self { path: paths::file_name(&path).to_string() })
+/// Creates a [SoundData] struct from an extracted WAV file, named after the
+/// XNB file it came from.
+///
+/// The format is left out if the WAV can't be read.
+fn open_sound_data(mut path: PathBuf) -> Result<SoundData, InvalidSound> {
+    if path.extension() != Some(OsStr::new("wav")) {
+        return Err(InvalidSound(path));
     }
-}
 
-impl Display for SoundData {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.path)
-    }
+    let format = fs::read(&path)
+        .ok()
+        .and_then(|b| WavData::parse(&b).ok())
+        .map(|w| SoundFormat::from(&w));
+
+    path.set_extension("xnb");
+    Ok(SoundData { file_name: paths::file_name(&path).to_string(), format })
 }
 
 struct InvalidSound(PathBuf);
@@ -42,7 +49,9 @@ impl Display for InvalidSound {
     }
 }
 
-pub fn generate_sound_ref(sound_dir: &PathBuf, refs: &PathBuf) -> io::Result<()> {
+pub fn generate_sound_ref(sound_dir: &PathBuf, refs: &PathBuf, game_version: Option<&str>)
+    -> io::Result<()>
+{
     output::divider("Generating sound reference...");
 
     let mut scan_data = ScanData::new("sounds");
@@ -73,7 +82,7 @@ pub fn generate_sound_ref(sound_dir: &PathBuf, refs: &PathBuf) -> io::Result<()>
     output::divider("Scan complete.");
     scan_data.print_results();
 
-    let slop = generate_slop(scan_data);
+    let slop = generate_slop(scan_data, game_version);
     output::divider("Writing SLOP to disk...");
 
     slop
@@ -86,18 +95,23 @@ pub fn generate_sound_ref(sound_dir: &PathBuf, refs: &PathBuf) -> io::Result<()>
 fn register_item(data: &mut SoundScanData, parent: &PathBuf, path: &PathBuf) {
     let key = paths::path_buf_to_key_name(parent);
 
-    match SoundData::open(path.clone()) {
+    match open_sound_data(path.clone()) {
         Ok(d) => data.push_valid(key, d),
         Err(b) => data.push_invalid(key, b),
     }
 }
 
-fn generate_slop(data: SoundScanData) -> Slop {
+fn generate_slop(data: SoundScanData, game_version: Option<&str>) -> Slop {
     output::divider("Converting to SLOP file...");
 
     let mut slop = Slop::new();
     slop.insert_unchecked(VERSION_KEY.to_string(), SOUND_REF_VERSION.to_string());
-    slop.insert_unchecked("!count".to_string(), data.joined_count().to_string());
+    slop.insert_unchecked(COUNT_KEY.to_string(), data.joined_count().to_string());
+    slop.insert_unchecked(GENERATED_KEY.to_string(), text_ref::today());
+
+    if let Some(v) = game_version {
+        slop.insert_unchecked(GAME_VERSION_KEY.to_string(), v);
+    }
 
     for (key, sounds) in data.valid_entries {
         let sounds: Vec<String> = sounds
@@ -0,0 +1,133 @@
+//! Handles `.tpackignore` files, which list the files `build` should leave out.
+
+use std::{fs, io, path::{Path, PathBuf}};
+
+use regex::Regex;
+
+use crate::{paths, static_file_data::IGNORE_FILE_NAME};
+
+/// The patterns of a `.tpackignore` file.
+///
+/// Each line holds one pattern, and lines starting with `#` are comments.
+/// `*` matches any part of a name, `?` matches a single character and `**`
+/// matches any amount of dirs.
+/// Patterns without a `/` match file names in any dir.
+pub struct IgnoreList {
+    root_dir: PathBuf,
+    patterns: Vec<Regex>,
+}
+
+impl IgnoreList {
+    /// Opens the `.tpackignore` in `root_dir`.
+    /// If there isn't one, nothing will be ignored.
+    pub fn open(root_dir: &PathBuf) -> io::Result<Self> {
+        let path = paths::push(root_dir, IGNORE_FILE_NAME);
+
+        let patterns = if path.is_file() {
+            fs::read_to_string(path)?
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(pattern_to_regex)
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Ok(Self { root_dir: root_dir.clone(), patterns })
+    }
+
+    /// Returns `true` if `path` matches any of the patterns.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let relative_path = match path.strip_prefix(&self.root_dir) {
+            Ok(p) => p.to_str().expect(paths::EXPECT_UTF8_PATH).replace('\\', "/"),
+            Err(_) => return false,
+        };
+
+        self.patterns.iter().any(|p| p.is_match(&relative_path))
+    }
+}
+
+fn pattern_to_regex(pattern: &str) -> Regex {
+    let pattern = pattern.trim_start_matches('/');
+
+    let mut regex = if pattern.contains('/') {
+        String::from("^")
+    } else {
+        String::from("^(?:.*/)?")
+    };
+
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    regex.push('$');
+    Regex::new(&regex).expect("expected escaped pattern to be a valid regex")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignore_list(patterns: &[&str]) -> IgnoreList {
+        IgnoreList {
+            root_dir: PathBuf::from("pack"),
+            patterns: patterns.iter().map(|p| pattern_to_regex(p)).collect(),
+        }
+    }
+
+    fn is_ignored(list: &IgnoreList, path: &str) -> bool {
+        list.is_ignored(&Path::new("pack").join(path))
+    }
+
+    #[test]
+    fn matches_names_in_any_dir() {
+        let list = ignore_list(&["*.psd", "Thumbs.db"]);
+
+        assert!(is_ignored(&list, "a.psd"));
+        assert!(is_ignored(&list, "Content/Images/Items/a.psd"));
+        assert!(is_ignored(&list, "Content/Thumbs.db"));
+        assert!(!is_ignored(&list, "Content/a.psd.png"));
+        assert!(!is_ignored(&list, "Content/NotThumbs.db"));
+    }
+
+    #[test]
+    fn matches_single_dirs_with_a_star() {
+        let list = ignore_list(&["Content/*/Drafts.png"]);
+
+        assert!(is_ignored(&list, "Content/Images/Drafts.png"));
+        assert!(!is_ignored(&list, "Content/Images/Items/Drafts.png"));
+        assert!(!is_ignored(&list, "Drafts.png"));
+    }
+
+    #[test]
+    fn matches_any_amount_of_dirs_with_two_stars() {
+        let list = ignore_list(&["/Content/**/wip/**"]);
+
+        assert!(is_ignored(&list, "Content/wip/a.png"));
+        assert!(is_ignored(&list, "Content/Images/Items/wip/Sub/a.png"));
+        assert!(!is_ignored(&list, "Other/wip/a.png"));
+        assert!(!is_ignored(&list, "Content/wipe/a.png"));
+    }
+
+    #[test]
+    fn ignores_paths_outside_the_root() {
+        assert!(!ignore_list(&["*"]).is_ignored(Path::new("other/a.png")));
+    }
+}
@@ -1,8 +1,10 @@
-use std::{path::PathBuf, fmt::Display, str::FromStr};
+use std::{error::Error, fmt::Display, fs, path::PathBuf, str::FromStr};
 
-use imagesize::{ImageSize, ImageError};
+use imagesize::ImageSize;
+use png::{ColorType, Decoder, Transformations};
+use xxhash_rust::xxh3::xxh3_64;
 
-use crate::paths;
+use crate::{paths, png_data::{InvalidPng, PngWarning}, suggest};
 
 /// Error returned in [ImageData]'s implementation of [FromStr].
 #[derive(Debug, PartialEq, Eq)]
@@ -15,16 +17,38 @@ pub struct ImageData {
     pub file_name: String,
 
     /// The Image's size.
-    pub size: ImageSize,
+    /// Missing in references older than version 1.
+    pub size: Option<ImageSize>,
+
+    /// The hash of the file's contents.
+    /// Missing in references older than version 2.
+    pub hash: Option<u64>,
+
+    /// The hash of the image's pixels, which ignores how the PNG was encoded.
+    /// Only stored if asked for when generating the reference.
+    pub pixel_hash: Option<u64>,
+}
+
+/// How a pack image is identical to its vanilla counterpart.
+pub enum VanillaCopy {
+    Bytes,
+    Pixels,
 }
 
 impl ImageData {
     /// Creates an [ImageData] struct from an image file.
-    pub fn open(path: &PathBuf) -> Result<Self, ImageError> {
+    ///
+    /// The pixel hash is only calculated if `with_pixel_hash` is `true`, as it
+    /// needs to decode the whole image.
+    pub fn open(path: &PathBuf, with_pixel_hash: bool) -> Result<Self, Box<dyn Error>> {
         let file_name = paths::file_name(path).to_string();
-        let size = imagesize::size(path)?;
+        let bytes = fs::read(path)?;
+        let size = imagesize::blob_size(&bytes)?;
+
+        let hash = Some(xxh3_64(&bytes));
+        let pixel_hash = if with_pixel_hash { pixel_hash(&bytes) } else { None };
 
-        Ok(Self { file_name, size })
+        Ok(Self { file_name, size: Some(size), hash, pixel_hash })
     }
 
     /// Returns `true` if the image is valid.
@@ -38,46 +62,120 @@ impl ImageData {
         let path = paths::push(dir, file_name);
 
         if self.file_name != file_name {
-            return Err(InvalidImage::BadName(path));
+            return Err(InvalidImage::BadName(path, vec![]));
         }
 
+        let Some(expected) = self.size else {
+            return Ok(());
+        };
+
         let size = imagesize::size(&path)
             .expect(&format!("failed to open {path:?}"));
 
-        if size == self.size {
+        if size == expected {
             Ok(())
         } else {
-            Err(InvalidImage::BadSize(path, size, self.size))
+            Err(InvalidImage::BadSize(path, size, expected))
+        }
+    }
+
+    /// Returns how the image in `bytes` is identical to this vanilla image,
+    /// if it is.
+    ///
+    /// Pixels are only compared if the files differ and the reference holds
+    /// a pixel hash.
+    pub fn find_vanilla_copy(&self, bytes: &[u8]) -> Option<VanillaCopy> {
+        if self.hash == Some(xxh3_64(bytes)) {
+            return Some(VanillaCopy::Bytes);
         }
+
+        match self.pixel_hash {
+            Some(h) if Some(h) == pixel_hash(bytes) => Some(VanillaCopy::Pixels),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the hash of a PNG's size and pixels, once converted to 8-bit RGBA.
+/// Returns [None] if the PNG can't be decoded.
+pub fn pixel_hash(bytes: &[u8]) -> Option<u64> {
+    let mut decoder = Decoder::new(bytes);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+
+    let mut reader = decoder.read_info().ok()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).ok()?;
+    let pixels = &buffer[..info.buffer_size()];
+
+    let mut rgba = Vec::with_capacity(info.width as usize * info.height as usize * 4);
+    rgba.extend_from_slice(&info.width.to_be_bytes());
+    rgba.extend_from_slice(&info.height.to_be_bytes());
+
+    match info.color_type {
+        ColorType::Rgba => rgba.extend_from_slice(pixels),
+        ColorType::Rgb => pixels.chunks(3).for_each(|p| rgba.extend([p[0], p[1], p[2], 255])),
+        ColorType::GrayscaleAlpha => pixels.chunks(2).for_each(|p| rgba.extend([p[0], p[0], p[0], p[1]])),
+        ColorType::Grayscale => pixels.iter().for_each(|&p| rgba.extend([p, p, p, 255])),
+        ColorType::Indexed => return None,
     }
+
+    Some(xxh3_64(&rgba))
 }
 
 impl Display for ImageData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}x{}", self.file_name, self.size.width, self.size.height)
+        write!(f, "{}", self.file_name)?;
+
+        if let Some(s) = &self.size {
+            write!(f, ":{}x{}", s.width, s.height)?;
+        }
+
+        if let Some(h) = self.hash {
+            write!(f, ":{h:016x}")?;
+        }
+
+        if let Some(h) = self.pixel_hash {
+            write!(f, ":{h:016x}")?;
+        }
+
+        Ok(())
     }
 }
 
 impl FromStr for ImageData {
     type Err = ParseImageDataError;
 
+    /// Parses `name:WxH`, optionally followed by `:hash` and `:pixel_hash`.
+    /// References older than version 1 only hold the `name`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (file_name, size) = s
-            .split_once(':')
-            .ok_or(ParseImageDataError)?;
+        let mut parts = s.split(':');
 
-        let file_name = file_name.to_string();
+        let file_name = parts
+            .next()
+            .ok_or(ParseImageDataError)?
+            .to_string();
 
-        let (width, height) = size
-            .split_once('x')
-            .ok_or(ParseImageDataError)?;
+        let size = match parts.next() {
+            Some(s) => {
+                let (width, height) = s.split_once('x').ok_or(ParseImageDataError)?;
 
-        let size = ImageSize {
-            width: width.parse().map_err(|_| ParseImageDataError)?,
-            height: height.parse().map_err(|_| ParseImageDataError)?,
+                Some(ImageSize {
+                    width: width.parse().map_err(|_| ParseImageDataError)?,
+                    height: height.parse().map_err(|_| ParseImageDataError)?,
+                })
+            }
+            None => None,
         };
 
-        Ok(Self { file_name, size })
+        let mut parse_hash = || match parts.next() {
+            Some(h) => u64::from_str_radix(h, 16).map(Some).map_err(|_| ParseImageDataError),
+            None => Ok(None),
+        };
+
+        let hash = parse_hash()?;
+        let pixel_hash = parse_hash()?;
+
+        Ok(Self { file_name, size, hash, pixel_hash })
     }
 }
 
@@ -85,21 +183,65 @@ impl FromStr for ImageData {
 /// These aren't fatal; instead, all errors of this type are collected and
 /// displayed once the scan ends.
 pub enum InvalidImage {
-    BadName(PathBuf),
+    /// Holds the path and the most similar names in the reference.
+    BadName(PathBuf, Vec<String>),
     BadSize(PathBuf, ImageSize, ImageSize),
+
+    /// Holds the path and the name in the reference that only differs from it
+    /// in letter case.
+    CaseMismatch(PathBuf, String),
+
+    /// Holds the path and the path of the format the game uses instead.
+    Overridden(PathBuf, PathBuf),
+
+    /// Holds the path and what is wrong with the file's contents.
+    BadFormat(PathBuf, InvalidPng),
+
+    /// Holds the path and why the game may load the file badly.
+    /// Unlike the others, these images are still copied by `build`.
+    LoadsBadly(PathBuf, PngWarning),
+
+    /// Holds the path and how it is identical to the vanilla image.
+    VanillaCopy(PathBuf, VanillaCopy),
 }
 
 impl Display for InvalidImage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::BadName(p) => {
+            Self::BadName(p, s) => {
                 let file_name = paths::file_name(p);
                 write!(
                     f,
-                    "{:?}\t: The name `{file_name}` was not found in the image reference.",
+                    "{:?}\t: The name `{file_name}` was not found in the image reference.{}",
+                    display_path_pretty(p),
+                    suggest::did_you_mean(s),
+                )
+            }
+            Self::CaseMismatch(p, n) => write!(
+                f,
+                "{:?}\t: Only the letter case differs from `{n}` in the reference. \
+                This breaks on case-sensitive systems.",
+                display_path_pretty(p),
+            ),
+            Self::VanillaCopy(p, c) => {
+                let what = match c {
+                    VanillaCopy::Bytes => "Identical",
+                    VanillaCopy::Pixels => "Pixel-for-pixel identical",
+                };
+                write!(
+                    f,
+                    "{:?}\t: {what} to the vanilla image. It only adds download size.",
                     display_path_pretty(p),
                 )
             }
+            Self::Overridden(p, o) => write!(
+                f,
+                "{:?}\t: `{}` is used instead, so this file is ignored.",
+                display_path_pretty(p),
+                paths::file_name(o),
+            ),
+            Self::BadFormat(p, b) => write!(f, "{:?}\t: {b}", display_path_pretty(p)),
+            Self::LoadsBadly(p, w) => write!(f, "{:?}\t: {w}", display_path_pretty(p)),
             Self::BadSize(p, bad_s, good_s) => {
                 let (bw, bh) = (bad_s.width, bad_s.height);
                 let (gw, gh) = (good_s.width, good_s.height);
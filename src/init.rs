@@ -0,0 +1,212 @@
+//! Handles the creation of new Resource Packs.
+
+use std::{error::Error, fs, io::{self, BufRead, Write}, path::PathBuf};
+
+use serde_json::json;
+
+use crate::{
+    config::PackConfig,
+    output,
+    paths,
+    refs,
+    static_file_data::{CONFIG_FILE_NAME, ICON_FILE_NAME, IGNORE_FILE_NAME, PACK_FILE_NAME},
+};
+
+/// The dirs the game reads assets from, with the exact casing it expects.
+const CONTENT_DIRS: [&str; 4] = [
+    "Content/Images",
+    "Content/Sounds",
+    "Content/Music",
+    "Content/Localization",
+];
+
+/// The size of the placeholder icon.
+const ICON_SIZE: u32 = 80;
+
+/// The color of the placeholder icon, as RGBA.
+const ICON_COLOR: [u8; 4] = [0x3E, 0x8E, 0x41, 0xFF];
+
+const STARTER_IGNORE_FILE: &str = "\
+# Files matching these patterns are left out by `build`.
+# One pattern per line. `*` matches any part of a name and `**` any amount of dirs.
+# Patterns without a `/` match file names in any dir.
+*.psd
+*.kra
+*.aseprite
+*.xcf
+Thumbs.db
+desktop.ini
+";
+
+/// The metadata written into `pack.json`.
+/// Missing values are asked for through the command line.
+pub struct PackInfo {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub version: Option<String>,
+
+    /// The version of the game the pack targets.
+    /// Defaults to the one the references were generated from.
+    pub game_version: Option<String>,
+}
+
+pub fn init_resource_pack(root_dir: &PathBuf, refs: &PathBuf, info: PackInfo)
+    -> Result<(), Box<dyn Error>>
+{
+    output::info("ACTION - Initialize Resource Pack");
+
+    let pack_file = paths::push(root_dir, PACK_FILE_NAME);
+
+    if pack_file.exists() {
+        return Err(format!("{pack_file:?} already exists").into());
+    }
+
+    let default_name = root_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("My Resource Pack")
+        .to_string();
+
+    let name = info.name.map_or_else(|| prompt("Name", &default_name), Ok)?;
+    let author = info.author.map_or_else(|| prompt("Author", ""), Ok)?;
+    let version = info.version.map_or_else(|| prompt("Version", "1.0"), Ok)?;
+    let (major, minor) = parse_version(&version)?;
+
+    output::announce_path("Creating", root_dir);
+
+    for dir in CONTENT_DIRS {
+        fs::create_dir_all(paths::push(root_dir, dir))?;
+    }
+
+    let pack = json!({
+        "Name": name,
+        "Author": author,
+        "Description": "Describe your Resource Pack here.",
+        "Version": { "major": major, "minor": minor },
+    });
+
+    fs::write(pack_file, serde_json::to_string_pretty(&pack)?)?;
+
+    // Files that already exist are kept, as the dir may already hold a pack.
+    let icon_file = paths::push(root_dir, ICON_FILE_NAME);
+
+    if !icon_file.exists() {
+        write_placeholder_icon(&icon_file)?;
+    }
+
+    let ignore_file = paths::push(root_dir, IGNORE_FILE_NAME);
+
+    if !ignore_file.exists() {
+        fs::write(ignore_file, STARTER_IGNORE_FILE)?;
+    }
+
+    if paths::push(root_dir, CONFIG_FILE_NAME).exists() {
+        output::warn(&format!("`{CONFIG_FILE_NAME}` already exists, so it was left as is."));
+    } else {
+        // The reference is stored relative to the pack, so the config still
+        // works after the dirs are moved together or shared.
+        let reference =
+            paths::relative_path(&fs::canonicalize(refs)?, &fs::canonicalize(root_dir)?);
+        let game_version = info.game_version.or_else(|| {
+            refs::game_versions(refs).into_iter().find_map(|(_, v)| v)
+        });
+
+        PackConfig { reference: Some(reference), game_version }.save(root_dir)?;
+    }
+
+    output::divider("Resource Pack created.");
+    Ok(())
+}
+
+/// Asks for a value through the command line, returning `default` if the
+/// answer is empty.
+fn prompt(what: &str, default: &str) -> io::Result<String> {
+    let mut stdout = io::stdout().lock();
+
+    if default.is_empty() {
+        write!(stdout, "{what}: ")?;
+    } else {
+        write!(stdout, "{what} [{default}]: ")?;
+    }
+
+    stdout.flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default.to_string() } else { answer.to_string() })
+}
+
+/// Parses a `major.minor` version string.
+///
+/// Fails if either part is not a positive integer.
+fn parse_version(version: &str) -> Result<(u32, u32), Box<dyn Error>> {
+    let (major, minor) = version.split_once('.').unwrap_or((version, "0"));
+    let invalid = || format!("`{version}` is not a valid version, expected `major.minor`");
+
+    let major = major.trim().parse().map_err(|_| invalid())?;
+    let minor = minor.trim().parse().map_err(|_| invalid())?;
+
+    Ok((major, minor))
+}
+
+fn write_placeholder_icon(path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let mut encoder = png::Encoder::new(fs::File::create(path)?, ICON_SIZE, ICON_SIZE);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let pixels = ICON_COLOR.repeat((ICON_SIZE * ICON_SIZE) as usize);
+    encoder.write_header()?.write_image_data(&pixels)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> PackInfo {
+        PackInfo {
+            name: Some("Pack".to_string()),
+            author: Some("Author".to_string()),
+            version: Some("1.2".to_string()),
+            game_version: None,
+        }
+    }
+
+    #[test]
+    fn keeps_existing_files() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let refs = tempfile::tempdir().unwrap();
+        let root_path = root_dir.path().to_path_buf();
+
+        fs::write(root_dir.path().join(ICON_FILE_NAME), "icon").unwrap();
+        fs::write(root_dir.path().join(CONFIG_FILE_NAME), "config").unwrap();
+
+        init_resource_pack(&root_path, &refs.path().to_path_buf(), info()).unwrap();
+
+        assert_eq!(fs::read_to_string(root_dir.path().join(ICON_FILE_NAME)).unwrap(), "icon");
+        assert_eq!(fs::read_to_string(root_dir.path().join(CONFIG_FILE_NAME)).unwrap(), "config");
+        assert!(root_dir.path().join(PACK_FILE_NAME).is_file());
+        assert!(root_dir.path().join("Content/Images").is_dir());
+    }
+
+    #[test]
+    fn refuses_to_replace_pack_files() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let refs = tempfile::tempdir().unwrap();
+        fs::write(root_dir.path().join(PACK_FILE_NAME), "{}").unwrap();
+
+        let root_path = root_dir.path().to_path_buf();
+        assert!(init_resource_pack(&root_path, &refs.path().to_path_buf(), info()).is_err());
+        assert!(!root_dir.path().join(ICON_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(parse_version("1.2").unwrap(), (1, 2));
+        assert_eq!(parse_version("3").unwrap(), (3, 0));
+        assert!(parse_version("1.x").is_err());
+    }
+}
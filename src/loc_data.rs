@@ -0,0 +1,84 @@
+//! Data about the game's vanilla translations.
+
+use std::{collections::HashMap, error::Error, path::PathBuf};
+
+/// The locales the game supports, in the same order as their columns in
+/// `Loc.csv`.
+pub const LOCALES: [&str; 9] = [
+    "en-US", "de-DE", "it-IT", "fr-FR", "es-ES", "ru-RU", "zh-Hans", "pt-BR", "pl-PL",
+];
+
+/// Returns the locale of a translation column, based on its header.
+/// Headers that aren't locales fall back to the order of [LOCALES].
+///
+/// `index` does not count the key column.
+pub fn locale_of_column(header: &str, index: usize) -> &'static str {
+    match LOCALES.iter().find(|l| l.eq_ignore_ascii_case(header.trim())) {
+        Some(l) => l,
+        None => LOCALES.get(index).unwrap_or(&""),
+    }
+}
+
+/// The vanilla value of every key, in each locale.
+/// Taken from the `loc_values.csv` file.
+pub struct LocValues {
+    pub locales: Vec<String>,
+
+    /// Holds the values of each key, in the same order as `locales`.
+    /// Empty values mean the key doesn't exist in that locale.
+    values: HashMap<String, Vec<String>>,
+}
+
+impl LocValues {
+    /// Opens a CSV file whose first column holds the keys and whose other
+    /// columns hold the values in each locale.
+    ///
+    /// The header row holds the locale of each column.
+    /// (see [locale_of_column])
+    pub fn open(path: &PathBuf) -> Result<Self, Box<dyn Error>> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+
+        let locales = reader
+            .headers()?
+            .iter()
+            .skip(1)
+            .enumerate()
+            .map(|(i, h)| locale_of_column(h, i).to_string())
+            .collect();
+
+        let mut values = HashMap::new();
+
+        for record in reader.records() {
+            let record = record?;
+
+            if let Some(key) = record.get(0) {
+                values.insert(key.to_string(), record.iter().skip(1).map(String::from).collect());
+            }
+        }
+
+        Ok(Self { locales, values })
+    }
+
+    /// Returns the vanilla value of `key` in `locale`, if it exists.
+    pub fn get(&self, key: &str, locale: &str) -> Option<&str> {
+        let index = self.locales.iter().position(|l| l == locale)?;
+
+        self.values
+            .get(key)?
+            .get(index)
+            .map(String::as_str)
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Returns every key that exists in any locale.
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.values.keys()
+    }
+
+    /// Returns every key that exists in `locale`.
+    pub fn keys_in<'a>(&'a self, locale: &'a str) -> impl Iterator<Item = &'a String> {
+        self.values
+            .keys()
+            .filter(move |k| self.get(k, locale).is_some())
+    }
+}
@@ -1,16 +1,29 @@
-use std::error::Error;
+use std::{error::Error, path::PathBuf};
 
 use clap::{self, Parser};
 use clio::ClioPath;
 
 mod build;
+mod config;
 mod gen;
+mod ignore;
 mod image_data;
+mod init;
+mod loc_data;
+mod music_data;
 mod output;
 mod paths;
+mod png_data;
+mod refs;
 mod scan;
 mod scanner;
+mod sound_data;
 mod static_file_data;
+mod suggest;
+mod text_ref;
+mod wav;
+mod xnb;
+mod xwb;
 
 /// Tool for diagnosing Terraria Resource Packs.
 #[derive(Parser)]
@@ -19,6 +32,9 @@ struct CliArgs {
     /// The action to be performed.
     action: String,
 
+    /// Extra arguments for the action. Not used by all commands.
+    args: Vec<String>,
+
     /// Input path directory. Not used by all commands.
     #[clap(short, long,
         value_parser = clap::value_parser!(ClioPath).exists().is_dir(),
@@ -34,11 +50,40 @@ struct CliArgs {
     output: ClioPath,
 
     /// Reference path directory. Not used by all commands.
+    /// Defaults to the one in the pack's config file, or `.` if there is none.
     #[clap(short, long,
         value_parser = clap::value_parser!(ClioPath).exists().is_dir(),
-        default_value = ".",
     )]
-    reference: ClioPath,
+    reference: Option<ClioPath>,
+
+    /// Name of the new pack. Only used by `init`.
+    #[clap(long)]
+    name: Option<String>,
+
+    /// Author of the new pack. Only used by `init`.
+    #[clap(long)]
+    author: Option<String>,
+
+    /// Version of the new pack, as `major.minor`. Only used by `init`.
+    #[clap(long)]
+    pack_version: Option<String>,
+
+    /// Version of the game the assets were extracted from (for `gen`), or the
+    /// pack targets (for `init`). Only used by `gen` and `init`.
+    #[clap(long)]
+    game_version: Option<String>,
+
+    /// Also store a hash of each image's pixels. Only used by `gen`.
+    #[clap(long)]
+    pixel_hash: bool,
+
+    /// Leave out images identical to vanilla ones. Only used by `build`.
+    #[clap(long)]
+    skip_vanilla: bool,
+
+    /// Convert WAV sound effects into XNB files. Only used by `build`.
+    #[clap(long)]
+    convert_sounds: bool,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -47,12 +92,44 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let input = args.input.path().to_path_buf();
     let output = args.output.to_path_buf();
-    let reference = args.reference.to_path_buf();
+
+    let reference = match args.reference {
+        Some(r) => r.to_path_buf(),
+        None => config::PackConfig::open(&input)?
+            .and_then(|c| c.reference_dir(&input))
+            .unwrap_or(PathBuf::from(".")),
+    };
 
     match args.action.as_str() {
-        "gen" => gen::generate_references(&input, &output)?,
+        "init" => {
+            let root_dir = args.args.first().map_or(output, PathBuf::from);
+            let info = init::PackInfo {
+                name: args.name,
+                author: args.author,
+                version: args.pack_version,
+                game_version: args.game_version,
+            };
+
+            init::init_resource_pack(&root_dir, &reference, info)?
+        }
+        "gen" => {
+            let options = gen::GenOptions {
+                pixel_hashes: args.pixel_hash,
+                game_version: args.game_version,
+            };
+
+            gen::generate_references(&input, &output, options)?
+        }
         "scan" => scan::scan_resource_pack(&input, &reference)?,
-        "build" => build::build_resource_pack(&input, &output, &reference)?,
+        "refs" => refs::run_refs_action(&args.args, &reference, &input)?,
+        "build" => {
+            let options = build::BuildOptions {
+                skip_vanilla: args.skip_vanilla,
+                convert_sounds: args.convert_sounds,
+            };
+
+            build::build_resource_pack(&input, &output, &reference, options)?
+        }
         a => panic!("invalid action `{a}`, run with `--help` for info"),
     }
 
@@ -0,0 +1,463 @@
+//! Reads the container and stream info of music files.
+
+use std::fmt::Display;
+
+use crate::wav::WavData;
+
+/// The size of the header of an Ogg page, without its segment table.
+const OGG_PAGE_HEADER_SIZE: usize = 27;
+
+/// The flag set on the last page of an Ogg stream.
+const OGG_END_OF_STREAM: u8 = 0x04;
+
+/// The size of an MP3 frame header.
+const MP3_HEADER_SIZE: usize = 4;
+
+/// The size of the ID3v1 tag some MP3 files end with.
+const ID3V1_SIZE: usize = 128;
+
+/// The bitrates of MPEG-1 Layer III frames, in kbps, by their index.
+const MP3_BITRATES_V1: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+
+/// The bitrates of MPEG-2 and 2.5 Layer III frames, in kbps, by their index.
+const MP3_BITRATES_V2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// The sample rates of MPEG-1 frames, by their index.
+const MP3_SAMPLE_RATES_V1: [u32; 3] = [44100, 48000, 32000];
+
+/// The containers music can come in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MusicFormat {
+    Wav,
+    Ogg,
+    Mp3,
+}
+
+impl MusicFormat {
+    /// Returns the format `extension` stands for, ignoring letter case.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "wav" => Some(Self::Wav),
+            "ogg" => Some(Self::Ogg),
+            "mp3" => Some(Self::Mp3),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a file from its first bytes.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WAVE") {
+            Some(Self::Wav)
+        } else if bytes.starts_with(b"OggS") {
+            Some(Self::Ogg)
+        } else if bytes.starts_with(b"ID3") || read_mp3_header(bytes, 0).is_some() {
+            Some(Self::Mp3)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for MusicFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wav => write!(f, "WAV"),
+            Self::Ogg => write!(f, "Ogg Vorbis"),
+            Self::Mp3 => write!(f, "MP3"),
+        }
+    }
+}
+
+/// The possible problems with the contents of a music file.
+pub enum InvalidMusicData {
+    Unknown,
+    Truncated,
+
+    /// Holds what is corrupt.
+    Corrupt(String),
+
+    /// Holds what isn't supported.
+    Unsupported(String),
+}
+
+impl Display for InvalidMusicData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "Not a WAV, Ogg Vorbis or MP3 file."),
+            Self::Truncated => write!(f, "The stream ends early. (truncated)"),
+            Self::Corrupt(r) => write!(f, "The stream is corrupt: {r}."),
+            Self::Unsupported(r) => write!(f, "Unsupported by the game: {r}."),
+        }
+    }
+}
+
+/// Info about the stream of a music file.
+pub struct MusicInfo {
+    pub format: MusicFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_ms: u64,
+}
+
+impl MusicInfo {
+    /// Sniffs the container of the music file in `bytes` and reads through its
+    /// stream.
+    pub fn read(bytes: &[u8]) -> Result<Self, InvalidMusicData> {
+        match MusicFormat::sniff(bytes) {
+            Some(MusicFormat::Wav) => read_wav(bytes),
+            Some(MusicFormat::Ogg) => read_ogg(bytes),
+            Some(MusicFormat::Mp3) => read_mp3(bytes),
+            None => Err(InvalidMusicData::Unknown),
+        }
+    }
+}
+
+impl Display for MusicInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let channels = match self.channels {
+            1 => "mono".to_string(),
+            2 => "stereo".to_string(),
+            c => format!("{c} channels"),
+        };
+
+        let seconds = self.duration_ms / 1000;
+
+        write!(
+            f,
+            "{}, {} Hz, {channels}, {}:{:02}",
+            self.format,
+            self.sample_rate,
+            seconds / 60,
+            seconds % 60,
+        )
+    }
+}
+
+fn read_wav(bytes: &[u8]) -> Result<MusicInfo, InvalidMusicData> {
+    use crate::wav::InvalidWav;
+
+    let wav = WavData::parse(bytes).map_err(|e| match e {
+        InvalidWav::Truncated => InvalidMusicData::Truncated,
+        InvalidWav::NotPcm(_) | InvalidWav::BadBitDepth(_) => {
+            InvalidMusicData::Unsupported(e.to_string().trim_end_matches('.').to_lowercase())
+        }
+        e => InvalidMusicData::Corrupt(e.to_string().trim_end_matches('.').to_lowercase()),
+    })?;
+
+    Ok(MusicInfo {
+        format: MusicFormat::Wav,
+        sample_rate: wav.sample_rate,
+        channels: wav.channels,
+        duration_ms: wav.duration_ms() as u64,
+    })
+}
+
+/// Walks through the pages of an Ogg file, checking their CRCs.
+/// The stream info is read from the Vorbis identification header.
+fn read_ogg(bytes: &[u8]) -> Result<MusicInfo, InvalidMusicData> {
+    let mut offset = 0;
+    let mut stream = None;
+    let mut last_granule = 0;
+    let mut last_flags = 0;
+
+    while offset < bytes.len() {
+        let header = bytes
+            .get(offset..offset + OGG_PAGE_HEADER_SIZE)
+            .ok_or(InvalidMusicData::Truncated)?;
+
+        if !header.starts_with(b"OggS") {
+            return Err(InvalidMusicData::Corrupt(format!("lost sync at byte {offset}")));
+        }
+
+        let segment_count = header[26] as usize;
+        let table_end = offset + OGG_PAGE_HEADER_SIZE + segment_count;
+        let table = bytes
+            .get(offset + OGG_PAGE_HEADER_SIZE..table_end)
+            .ok_or(InvalidMusicData::Truncated)?;
+
+        let body_size: usize = table.iter().map(|s| *s as usize).sum();
+        let page = bytes.get(offset..table_end + body_size).ok_or(InvalidMusicData::Truncated)?;
+
+        // SAFETY: The slice is exactly 4 bytes long.
+        let crc = u32::from_le_bytes(header[22..26].try_into().unwrap());
+
+        if ogg_crc(page) != crc {
+            return Err(InvalidMusicData::Corrupt(format!("bad CRC in the page at byte {offset}")));
+        }
+
+        if stream.is_none() {
+            stream = Some(read_vorbis_header(&page[table_end - offset..])?);
+        }
+
+        // SAFETY: The slice is exactly 8 bytes long.
+        let granule = u64::from_le_bytes(header[6..14].try_into().unwrap());
+
+        // Pages with no finished packets have a granule position of -1.
+        if granule != u64::MAX {
+            last_granule = granule;
+        }
+
+        last_flags = header[5];
+        offset = table_end + body_size;
+    }
+
+    if last_flags & OGG_END_OF_STREAM == 0 {
+        return Err(InvalidMusicData::Truncated);
+    }
+
+    let (sample_rate, channels) = stream.ok_or(InvalidMusicData::Truncated)?;
+
+    Ok(MusicInfo {
+        format: MusicFormat::Ogg,
+        sample_rate,
+        channels,
+        duration_ms: last_granule * 1000 / sample_rate as u64,
+    })
+}
+
+/// Reads the sample rate and channel count from the first packet of an Ogg
+/// stream, which must be a Vorbis identification header.
+fn read_vorbis_header(packet: &[u8]) -> Result<(u32, u16), InvalidMusicData> {
+    if packet.starts_with(b"OpusHead") {
+        return Err(InvalidMusicData::Unsupported("Ogg Opus streams".to_string()));
+    }
+
+    if !packet.starts_with(b"\x01vorbis") || packet.len() < 16 {
+        return Err(InvalidMusicData::Unsupported("Ogg streams that aren't Vorbis".to_string()));
+    }
+
+    let channels = packet[11] as u16;
+    // SAFETY: The slice is exactly 4 bytes long.
+    let sample_rate = u32::from_le_bytes(packet[12..16].try_into().unwrap());
+
+    if channels == 0 || sample_rate == 0 {
+        return Err(InvalidMusicData::Corrupt("invalid Vorbis header".to_string()));
+    }
+
+    Ok((sample_rate, channels))
+}
+
+/// The CRC of every byte, using the polynomial of Ogg pages.
+static OGG_CRC_TABLE: [u32; 256] = ogg_crc_table();
+
+const fn ogg_crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04C1_1DB7 } else { crc << 1 };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+/// Calculates the CRC of an Ogg page, with its own CRC field taken as zero.
+fn ogg_crc(page: &[u8]) -> u32 {
+    let mut crc = 0u32;
+
+    for (i, byte) in page.iter().enumerate() {
+        let byte = if (22..26).contains(&i) { 0 } else { *byte };
+        crc = (crc << 8) ^ OGG_CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize];
+    }
+
+    crc
+}
+
+/// A parsed MP3 frame header.
+struct Mp3Frame {
+    sample_rate: u32,
+    channels: u16,
+    samples: u32,
+    length: usize,
+}
+
+/// Walks through the frames of an MP3 file, skipping its ID3 tags.
+fn read_mp3(bytes: &[u8]) -> Result<MusicInfo, InvalidMusicData> {
+    let mut offset = 0;
+
+    if bytes.starts_with(b"ID3") {
+        let size = bytes.get(6..10).ok_or(InvalidMusicData::Truncated)?;
+        // ID3v2 sizes are stored in 7-bit bytes.
+        let size = size.iter().fold(0, |s, b| (s << 7) | (*b as usize & 0x7F));
+        offset = 10 + size;
+    }
+
+    let mut first = None;
+    let mut total_samples = 0u64;
+
+    while offset < bytes.len() {
+        if bytes[offset..].starts_with(b"TAG") && bytes.len() - offset == ID3V1_SIZE {
+            break;
+        }
+
+        let frame = match read_mp3_header(bytes, offset) {
+            Some(f) => f,
+            None if first.is_none() => {
+                return Err(InvalidMusicData::Corrupt("no MPEG audio frames".to_string()))
+            }
+            // Anything after the frames, such as APEv2 or Lyrics3 tags,
+            // padding or an ID3v2 footer, is left to the decoder.
+            None => break,
+        };
+
+        if offset + frame.length > bytes.len() {
+            return Err(InvalidMusicData::Truncated);
+        }
+
+        total_samples += frame.samples as u64;
+        offset += frame.length;
+        first.get_or_insert((frame.sample_rate, frame.channels));
+    }
+
+    let (sample_rate, channels) = first.ok_or(InvalidMusicData::Truncated)?;
+
+    Ok(MusicInfo {
+        format: MusicFormat::Mp3,
+        sample_rate,
+        channels,
+        duration_ms: total_samples * 1000 / sample_rate as u64,
+    })
+}
+
+/// Parses the MPEG Layer III frame header at `offset`.
+/// Returns [None] if there is no valid header there.
+fn read_mp3_header(bytes: &[u8], offset: usize) -> Option<Mp3Frame> {
+    let header = bytes.get(offset..offset + MP3_HEADER_SIZE)?;
+
+    if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+
+    let version = (header[1] >> 3) & 0b11;
+    let layer = (header[1] >> 1) & 0b11;
+    let bitrate_index = (header[2] >> 4) as usize;
+    let sample_rate_index = ((header[2] >> 2) & 0b11) as usize;
+    let padding = ((header[2] >> 1) & 1) as usize;
+    let channel_mode = header[3] >> 6;
+
+    // Version 1 is reserved, and layer 1 stands for Layer III.
+    if version == 1 || layer != 1 || bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+        return None;
+    }
+
+    let is_v1 = version == 3;
+    let bitrates = if is_v1 { MP3_BITRATES_V1 } else { MP3_BITRATES_V2 };
+    let divisor = match version { 3 => 1, 2 => 2, _ => 4 };
+
+    let bitrate = bitrates[bitrate_index] * 1000;
+    let sample_rate = MP3_SAMPLE_RATES_V1[sample_rate_index] / divisor;
+    let samples = if is_v1 { 1152 } else { 576 };
+
+    Some(Mp3Frame {
+        sample_rate,
+        channels: if channel_mode == 3 { 1 } else { 2 },
+        samples,
+        length: (samples / 8 * bitrate / sample_rate) as usize + padding,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An Ogg page holding a single packet, with a valid CRC.
+    fn ogg_page(flags: u8, granule: u64, packet: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS\0".to_vec();
+        page.push(flags);
+        page.extend(granule.to_le_bytes());
+        page.extend([0; 12]);
+        page.push(1);
+        page.push(packet.len() as u8);
+        page.extend(packet);
+
+        let crc = ogg_crc(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    fn ogg() -> Vec<u8> {
+        let mut header = b"\x01vorbis\0\0\0\0\x02".to_vec();
+        header.extend(44100u32.to_le_bytes());
+
+        let mut bytes = ogg_page(0x02, 0, &header);
+        bytes.extend(ogg_page(OGG_END_OF_STREAM, 88200, b"audio"));
+        bytes
+    }
+
+    /// An MP3 file of MPEG-1 Layer III frames at 128 kbps, 44100 Hz and in
+    /// stereo.
+    fn mp3(frame_count: usize) -> Vec<u8> {
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x00];
+        frame.resize(417, 0);
+
+        let mut bytes = b"ID3\x04\0\0\0\0\0\x02\0\0".to_vec();
+        (0..frame_count).for_each(|_| bytes.extend(&frame));
+        bytes
+    }
+
+    #[test]
+    fn uses_the_ogg_crc() {
+        assert_eq!(ogg_crc(b"123456789"), 0x89A1_897F);
+    }
+
+    #[test]
+    fn reads_ogg_files() {
+        let info = MusicInfo::read(&ogg()).ok().unwrap();
+
+        assert!(info.format == MusicFormat::Ogg);
+        assert_eq!((info.sample_rate, info.channels, info.duration_ms), (44100, 2, 2000));
+    }
+
+    #[test]
+    fn rejects_truncated_ogg_files() {
+        let bytes = ogg();
+        assert!(matches!(MusicInfo::read(&bytes[..40]), Err(InvalidMusicData::Truncated)));
+        assert!(matches!(MusicInfo::read(&bytes[..45]), Err(InvalidMusicData::Truncated)));
+    }
+
+    #[test]
+    fn rejects_corrupt_ogg_files() {
+        let mut bytes = ogg();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        assert!(matches!(MusicInfo::read(&bytes), Err(InvalidMusicData::Corrupt(_))));
+
+        let opus = ogg_page(OGG_END_OF_STREAM, 0, b"OpusHead");
+        assert!(matches!(MusicInfo::read(&opus), Err(InvalidMusicData::Unsupported(_))));
+    }
+
+    #[test]
+    fn reads_mp3_files_with_trailing_tags() {
+        let mut bytes = mp3(10);
+        bytes.extend(b"APETAGEX\xD0\x07\0\0");
+        bytes.extend([0; 100]);
+
+        let info = MusicInfo::read(&bytes).ok().unwrap();
+
+        assert!(info.format == MusicFormat::Mp3);
+        assert_eq!((info.sample_rate, info.channels, info.duration_ms), (44100, 2, 261));
+    }
+
+    #[test]
+    fn rejects_truncated_mp3_files() {
+        let bytes = mp3(2);
+        let result = MusicInfo::read(&bytes[..bytes.len() - 1]);
+        assert!(matches!(result, Err(InvalidMusicData::Truncated)));
+    }
+
+    #[test]
+    fn rejects_corrupt_mp3_files() {
+        let mut bytes = mp3(2);
+        bytes[12] = 0;
+        assert!(matches!(MusicInfo::read(&bytes), Err(InvalidMusicData::Corrupt(_))));
+    }
+}
@@ -1,10 +1,12 @@
 //! Handles the printed output.
 
-use std::{path::PathBuf, io::{StdoutLock, self, Write}, fmt::Display};
+use std::{cmp::Ordering, path::PathBuf, io::{StdoutLock, self, Write}, fmt::Display};
 
 use ansi_term::{Color, Style, ANSIGenericString};
 use lazy_static::lazy_static;
 
+use crate::static_file_data::MAX_LIST_SIZE;
+
 lazy_static! {
     pub static ref DASH: ANSIGenericString<'static, str> = Color::Blue.bold().paint("-");
     pub static ref RED_DASH: ANSIGenericString<'static, str> = Color::Red.bold().paint("-");
@@ -57,3 +59,36 @@ where
         println!("{bullet} {item}");
     }
 }
+
+/// Prints how many problems were found in a single file, followed by the
+/// problems themselves, as a [titled_list].
+pub fn problem_list<D: Display>(problems: &[D]) {
+    let count = problems.len();
+    let count_str = Color::Red.bold().paint(count.to_string());
+
+    let title = match count.cmp(&1) {
+        Ordering::Less => {
+            println!("{} No problems found!", *DASH);
+            return;
+        }
+        Ordering::Equal => format!("Found {count_str} problem."),
+        Ordering::Greater => format!("Found {count_str} problems."),
+    };
+
+    titled_list(&*RED_DASH, &title, problems);
+}
+
+/// Prints `title`, followed by the first [MAX_LIST_SIZE] `items` on separate
+/// lines and how many were left out.
+pub fn titled_list<B, D>(dash: &B, title: &str, items: &[D])
+where
+    B: Display + ?Sized,
+    D: Display,
+{
+    println!("{dash} {title}");
+    bullet_list(format!("  {dash}"), items.iter().take(MAX_LIST_SIZE));
+
+    if items.len() > MAX_LIST_SIZE {
+        println!("  {dash} ... and {} more.", items.len() - MAX_LIST_SIZE);
+    }
+}
@@ -1,6 +1,6 @@
 //! Contains some path processing functions.
 
-use std::path::{PathBuf, Path};
+use std::path::{Component, PathBuf, Path};
 
 pub const EXPECT_UTF8_PATH: &str = "expected path to be a valid utf-8 string";
 
@@ -40,6 +40,25 @@ pub fn push<P>(base: &PathBuf, suffix: P) -> PathBuf where P: AsRef<Path> {
     result
 }
 
+/// Returns the path that leads from `base` to `path`, going up with `..` where
+/// needed.
+///
+/// Both paths should be absolute, or relative to the same dir.
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let shared = path.iter().zip(&base).take_while(|(p, b)| p == b).count();
+
+    let mut result: PathBuf = base[shared..].iter().map(|_| Component::ParentDir).collect();
+    result.extend(&path[shared..]);
+
+    if result.as_os_str().is_empty() {
+        result.push(Component::CurDir);
+    }
+
+    result
+}
+
 /// Prepares a path to be added to a reference file.
 #[inline]
 pub fn sanitize_path(mut path_dir: PathBuf, base_dir: &PathBuf) -> PathBuf {
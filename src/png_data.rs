@@ -0,0 +1,224 @@
+//! Checks the structure of PNG files, beyond what is needed to read their size.
+
+use std::fmt::Display;
+
+use crate::static_file_data::PNG_SIGNATURE;
+
+/// The possible problems with the structure of a PNG file, which make it
+/// unreadable.
+pub enum InvalidPng {
+    /// Holds the name of the format the file is really in, if it is known.
+    NotPng(Option<&'static str>),
+    Truncated,
+    BadHeader,
+    MissingEnd,
+
+    /// Holds the type of the chunk.
+    BadCrc(String),
+
+    /// Holds the bit depth and color type.
+    BadBitDepth(u8, u8),
+}
+
+impl Display for InvalidPng {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotPng(Some(n)) => write!(f, "This is a {n} file with a `.png` extension."),
+            Self::NotPng(None) => write!(f, "This is not a PNG file."),
+            Self::Truncated => write!(f, "The file ends in the middle of a chunk. (truncated)"),
+            Self::BadHeader => write!(f, "The file doesn't start with a valid `IHDR` chunk."),
+            Self::MissingEnd => write!(f, "The file has no `IEND` chunk. (truncated)"),
+            Self::BadCrc(c) => write!(f, "The `{c}` chunk is corrupt. (bad CRC)"),
+            Self::BadBitDepth(d, c) => {
+                write!(f, "Bit depth {d} is not allowed with color type {c}.")
+            }
+        }
+    }
+}
+
+/// The ways a valid PNG file may be loaded badly by the game.
+pub enum PngWarning {
+    /// Holds the bit depth.
+    LowBitDepth(u8),
+    SixteenBit,
+    Interlaced,
+}
+
+impl Display for PngWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LowBitDepth(d) => write!(
+                f,
+                "Uses {d} bits per pixel, which the game may load badly. Save it with 8.",
+            ),
+            Self::SixteenBit => write!(
+                f,
+                "Uses 16 bits per channel, which the game may load badly. Save it with 8.",
+            ),
+            Self::Interlaced => write!(
+                f,
+                "Is interlaced, which the game may load badly. Save it without interlacing.",
+            ),
+        }
+    }
+}
+
+/// Walks through the chunks of a PNG file, returning the first problem that
+/// makes it unreadable.
+///
+/// If it can be read, the first reason the game may load it badly is returned
+/// instead, if there is one.
+pub fn validate_png(bytes: &[u8]) -> Result<Option<PngWarning>, InvalidPng> {
+    if !bytes.starts_with(&PNG_SIGNATURE) {
+        return Err(InvalidPng::NotPng(sniff_format(bytes)));
+    }
+
+    let mut header = None;
+    let mut offset = PNG_SIGNATURE.len();
+
+    loop {
+        let chunk = bytes.get(offset..offset + 8).ok_or(InvalidPng::Truncated)?;
+        // SAFETY: `chunk` is exactly 8 bytes long.
+        let length = u32::from_be_bytes(chunk[..4].try_into().unwrap()) as usize;
+        let chunk_type = &chunk[4..];
+
+        let data_end = offset + 8 + length;
+        let data = bytes.get(offset + 8..data_end).ok_or(InvalidPng::Truncated)?;
+        let crc = bytes.get(data_end..data_end + 4).ok_or(InvalidPng::Truncated)?;
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(chunk_type);
+        hasher.update(data);
+
+        // SAFETY: `crc` is exactly 4 bytes long.
+        if hasher.finalize() != u32::from_be_bytes(crc.try_into().unwrap()) {
+            return Err(InvalidPng::BadCrc(String::from_utf8_lossy(chunk_type).to_string()));
+        }
+
+        if header.is_none() {
+            if chunk_type != b"IHDR" || length != 13 {
+                return Err(InvalidPng::BadHeader);
+            }
+
+            header = Some(data);
+        }
+
+        if chunk_type == b"IEND" {
+            break;
+        }
+
+        offset = data_end + 4;
+
+        if offset == bytes.len() {
+            return Err(InvalidPng::MissingEnd);
+        }
+    }
+
+    // SAFETY: The first chunk is always the header, or the function returns.
+    validate_header(header.unwrap())
+}
+
+/// Validates the data of the `IHDR` chunk.
+fn validate_header(header: &[u8]) -> Result<Option<PngWarning>, InvalidPng> {
+    let (bit_depth, color_type, interlace) = (header[8], header[9], header[12]);
+
+    let allowed_depths: &[u8] = match color_type {
+        0 => &[1, 2, 4, 8, 16],
+        3 => &[1, 2, 4, 8],
+        2 | 4 | 6 => &[8, 16],
+        _ => return Err(InvalidPng::BadHeader),
+    };
+
+    if !allowed_depths.contains(&bit_depth) {
+        return Err(InvalidPng::BadBitDepth(bit_depth, color_type));
+    }
+
+    let warning = if bit_depth == 16 {
+        Some(PngWarning::SixteenBit)
+    } else if bit_depth < 8 {
+        Some(PngWarning::LowBitDepth(bit_depth))
+    } else if interlace != 0 {
+        Some(PngWarning::Interlaced)
+    } else {
+        None
+    };
+
+    Ok(warning)
+}
+
+/// Guesses the format of a file that isn't a PNG from its first bytes.
+fn sniff_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("JPEG")
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some("WebP")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("GIF")
+    } else if bytes.starts_with(b"BM") {
+        Some("BMP")
+    } else if bytes.starts_with(b"XNB") {
+        Some("XNB")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a PNG file out of the data of its `IHDR` chunk, with an empty
+    /// `IDAT` chunk, as the pixels are never read.
+    fn png(bit_depth: u8, color_type: u8, interlace: u8) -> Vec<u8> {
+        let mut header = vec![0, 0, 0, 16, 0, 0, 0, 16];
+        header.extend([bit_depth, color_type, 0, 0, interlace]);
+
+        let mut bytes = PNG_SIGNATURE.to_vec();
+
+        for (chunk_type, data) in [(b"IHDR", header), (b"IDAT", vec![]), (b"IEND", vec![])] {
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(chunk_type);
+            hasher.update(&data);
+
+            bytes.extend((data.len() as u32).to_be_bytes());
+            bytes.extend(chunk_type);
+            bytes.extend(&data);
+            bytes.extend(hasher.finalize().to_be_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn accepts_8_bit_pngs() {
+        for color_type in [0, 2, 3, 4, 6] {
+            assert!(matches!(validate_png(&png(8, color_type, 0)), Ok(None)), "{color_type}");
+        }
+    }
+
+    #[test]
+    fn warns_about_pngs_the_game_may_load_badly() {
+        assert!(matches!(validate_png(&png(16, 6, 0)), Ok(Some(PngWarning::SixteenBit))));
+        assert!(matches!(validate_png(&png(4, 3, 0)), Ok(Some(PngWarning::LowBitDepth(4)))));
+        assert!(matches!(validate_png(&png(8, 6, 1)), Ok(Some(PngWarning::Interlaced))));
+    }
+
+    #[test]
+    fn rejects_truncated_pngs() {
+        let bytes = png(8, 6, 0);
+
+        assert!(matches!(validate_png(&bytes[..20]), Err(InvalidPng::Truncated)));
+        assert!(matches!(validate_png(&bytes[..bytes.len() - 12]), Err(InvalidPng::MissingEnd)));
+    }
+
+    #[test]
+    fn rejects_corrupt_pngs() {
+        let mut bytes = png(8, 6, 0);
+        bytes[20] ^= 0xFF;
+
+        assert!(matches!(validate_png(&bytes), Err(InvalidPng::BadCrc(c)) if c == "IHDR"));
+        assert!(matches!(validate_png(&png(8, 7, 0)), Err(InvalidPng::BadHeader)));
+        assert!(matches!(validate_png(&png(2, 2, 0)), Err(InvalidPng::BadBitDepth(2, 2))));
+        assert!(matches!(validate_png(b"\xFF\xD8\xFF\xE0"), Err(InvalidPng::NotPng(Some("JPEG")))));
+    }
+}
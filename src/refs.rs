@@ -0,0 +1,235 @@
+//! Handles the versions of the reference files and the `refs` action, which
+//! works on them directly.
+
+use std::{error::Error, path::PathBuf};
+
+use slop_rs::Slop;
+
+use crate::{
+    output,
+    paths,
+    static_file_data::{
+        Version,
+        COUNT_KEY,
+        GAME_VERSION_KEY,
+        IMAGE_REF_NAME,
+        IMAGE_REF_VERSION,
+        LOC_REF_NAME,
+        LOC_REF_VERSION,
+        MUSIC_REF_NAME,
+        MUSIC_REF_VERSION,
+        SOUND_REF_NAME,
+        SOUND_REF_VERSION,
+        VERSION_KEY,
+    },
+    text_ref::TextRef,
+};
+
+mod diff;
+
+/// A kind of reference file, and what each of its versions added.
+pub struct RefFile {
+    pub name: &'static str,
+    pub current: Version,
+
+    /// What is lost when reading a version older than the one it's paired
+    /// with, as a sentence fragment.
+    changes: &'static [(Version, &'static str)],
+}
+
+pub const IMAGE_REF: RefFile = RefFile {
+    name: IMAGE_REF_NAME,
+    current: IMAGE_REF_VERSION,
+    changes: &[
+        (1, "image sizes won't be checked"),
+        (2, "images identical to vanilla ones won't be found"),
+    ],
+};
+
+pub const SOUND_REF: RefFile = RefFile {
+    name: SOUND_REF_NAME,
+    current: SOUND_REF_VERSION,
+    changes: &[(1, "sound formats won't be compared")],
+};
+
+pub const MUSIC_REF: RefFile = RefFile {
+    name: MUSIC_REF_NAME,
+    current: MUSIC_REF_VERSION,
+    changes: &[(1, "the amount of songs won't be checked")],
+};
+
+pub const LOC_REF: RefFile = RefFile {
+    name: LOC_REF_NAME,
+    current: LOC_REF_VERSION,
+    changes: &[(1, "the amount of keys won't be checked")],
+};
+
+impl RefFile {
+    /// Checks the `version` of a reference of this kind, warning about what is
+    /// missing if it is older than the current one. It can still be read, as
+    /// every older version is upgraded in memory.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the reference is newer than this version of the tool.
+    pub fn check_version(&self, version: Version) {
+        let name = self.name;
+
+        if version > self.current {
+            panic!(
+                "`{name}` is version {version}, but this version of the tool only reads \
+                up to version {}. Update the tool, or run `gen` again with this version",
+                self.current,
+            );
+        }
+
+        let missing = self.missing_since(version);
+
+        if missing.is_empty() {
+            return;
+        }
+
+        output::warn(&format!(
+            "`{name}` is version {version}, so {}. Run `gen` again to update it.",
+            missing.join(" and "),
+        ));
+    }
+
+    /// Returns what is lost when reading a reference at `version`.
+    fn missing_since(&self, version: Version) -> Vec<&'static str> {
+        self.changes
+            .iter()
+            .filter(|(v, _)| *v > version)
+            .map(|(_, m)| *m)
+            .collect()
+    }
+}
+
+/// Returns the version in the [Slop]'s `!version` KV.
+/// Files written before the KV was added count as version 0.
+pub fn slop_version(slop: &Slop) -> Version {
+    slop.get(VERSION_KEY).map_or(0, |v| {
+        v
+            .string()
+            .expect("expected the slop file's `!version` kv to be a string kv")
+            .parse()
+            .expect("expected the slop file's `!version` kv to be a positive integer")
+    })
+}
+
+/// Checks the version of a [Slop] reference of the `file` kind.
+///
+/// ## Panics
+///
+/// Panics if the reference is newer than this version of the tool.
+pub fn check_slop(slop: &Slop, file: &RefFile) {
+    file.check_version(slop_version(slop));
+}
+
+/// Returns the version of the game each reference file in `ref_dir` was
+/// generated from, or [None] for the ones that don't record it.
+/// Missing or unreadable files are left out.
+pub fn game_versions(ref_dir: &PathBuf) -> Vec<(&'static str, Option<String>)> {
+    let mut versions = vec![];
+
+    for file in [&IMAGE_REF, &SOUND_REF] {
+        if let Ok(slop) = Slop::open(paths::push(ref_dir, file.name)) {
+            versions.push((file.name, slop.get_string(GAME_VERSION_KEY).cloned()));
+        }
+    }
+
+    for file in [&MUSIC_REF, &LOC_REF] {
+        if let Ok(reference) = TextRef::open(paths::push(ref_dir, file.name)) {
+            versions.push((file.name, reference.get(GAME_VERSION_KEY).map(String::from)));
+        }
+    }
+
+    versions
+}
+
+/// Runs the `refs` action, whose first arg is the subcommand.
+///
+/// `input` is only used by `diff`, which checks the pack in it if it has a
+/// `Content` dir.
+pub fn run_refs_action(args: &[String], ref_dir: &PathBuf, input: &PathBuf)
+    -> Result<(), Box<dyn Error>>
+{
+    output::info("ACTION - References");
+
+    match args.first().map(String::as_str) {
+        Some("upgrade") => upgrade_refs(ref_dir),
+        Some("diff") => {
+            let [_, old, new] = args else {
+                panic!("expected `refs diff <old dir> <new dir>`");
+            };
+
+            let pack_dir = Some(input).filter(|i| paths::push(i, "Content").is_dir());
+            diff::diff_refs(&PathBuf::from(old), &PathBuf::from(new), pack_dir)
+        }
+        Some(s) => panic!("invalid `refs` subcommand `{s}`, expected `upgrade` or `diff`"),
+        None => panic!("expected a `refs` subcommand, such as `upgrade` or `diff`"),
+    }
+}
+
+/// Rewrites every outdated text reference in `ref_dir` at the current version.
+///
+/// Slop references can't be upgraded, as what older versions lack (such as
+/// image sizes) can only be read from the extracted files. Outdated ones are
+/// listed instead.
+fn upgrade_refs(ref_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
+    output::announce_path("Upgrading references in", ref_dir);
+
+    for file in [&IMAGE_REF, &SOUND_REF] {
+        let path = paths::push(ref_dir, file.name);
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let version = slop_version(&Slop::open(&path)?);
+
+        if is_outdated(file, version) {
+            output::warn(&format!(
+                "`{}` is version {version}, which can't be upgraded, so {}. \
+                Run `gen` again to update it.",
+                file.name,
+                file.missing_since(version).join(" and "),
+            ));
+        }
+    }
+
+    for file in [&MUSIC_REF, &LOC_REF] {
+        let path = paths::push(ref_dir, file.name);
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let reference = TextRef::open(&path)?;
+
+        // The count is the only thing older versions lack, so these are
+        // upgraded fully. The generation date stays unknown.
+        if is_outdated(file, reference.version()) {
+            let header = vec![
+                (VERSION_KEY.to_string(), file.current.to_string()),
+                (COUNT_KEY.to_string(), reference.lines.len().to_string()),
+            ];
+
+            TextRef { header, lines: reference.lines }.save(&path)?;
+            output::divider(&format!("Upgraded `{}` to version {}.", file.name, file.current));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if a `file` reference at `version` is older than the current
+/// version, printing that it's up to date otherwise.
+fn is_outdated(file: &RefFile, version: Version) -> bool {
+    if version < file.current {
+        return true;
+    }
+
+    output::divider(&format!("`{}` is up to date.", file.name));
+    false
+}
@@ -0,0 +1,460 @@
+//! Compares the references of two game versions, and finds what the changes
+//! mean for a Resource Pack.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use imagesize::ImageSize;
+use slop_rs::Slop;
+use walkdir::WalkDir;
+
+use crate::{
+    output::{self, DASH, RED_DASH},
+    paths,
+    scan::{images, loc::{self, LocFile, LocFileType}, music, sounds},
+    static_file_data::{
+        IMAGE_REF_NAME,
+        LOC_REF_NAME,
+        MUSIC_REF_NAME,
+        SOUND_REF_NAME,
+    },
+};
+
+use super::{check_slop, IMAGE_REF, SOUND_REF};
+
+/// The kinds of assets in the references, along with the content dir they go
+/// in and the extensions the game looks for.
+#[derive(Clone, Copy)]
+enum AssetKind {
+    Image,
+    Sound,
+    Music,
+}
+
+impl AssetKind {
+    fn content_dir(&self) -> &'static str {
+        match self {
+            Self::Image => "Content/Images",
+            Self::Sound => "Content/Sounds",
+            Self::Music => "Content/Music",
+        }
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Self::Image => &images::EXTENSIONS,
+            Self::Sound => &sounds::EXTENSIONS,
+            Self::Music => &music::EXTENSIONS,
+        }
+    }
+
+    /// Returns the asset `name` without its extension.
+    ///
+    /// The extension is only removed if it's an accepted one, as song names
+    /// have none but may still hold dots.
+    fn stem<'a>(&self, name: &'a str) -> &'a str {
+        match name.rsplit_once('.') {
+            Some((s, e)) if self.extensions().iter().any(|x| x.eq_ignore_ascii_case(e)) => s,
+            _ => name,
+        }
+    }
+
+    /// Returns the file name the asset `name` should have, in the preferred
+    /// format.
+    fn file_name(&self, name: &str) -> String {
+        format!("{}.{}", self.stem(name), self.extensions()[0])
+    }
+
+    /// Returns the file the pack uses for the asset `name`, in any of the
+    /// accepted formats.
+    fn find_in_pack(&self, pack_dir: &PathBuf, name: &str) -> Option<PathBuf> {
+        let dir = paths::push(pack_dir, self.content_dir());
+        let stem = self.stem(name);
+
+        self.extensions()
+            .iter()
+            .map(|e| paths::push(&dir, format!("{stem}.{e}")))
+            .find(|p| p.is_file())
+    }
+}
+
+/// A change between the old and new references.
+pub enum RefChange {
+    Added(String),
+    Removed(String),
+
+    /// Holds the path and the old and new sizes.
+    Resized(String, ImageSize, ImageSize),
+}
+
+impl Display for RefChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(n) => write!(f, "`{n}`\t: New in the game."),
+            Self::Removed(n) => write!(f, "`{n}`\t: Removed from the game."),
+            Self::Resized(n, o, s) => write!(
+                f,
+                "`{n}`\t: Resized from {}x{} to {}x{}.",
+                o.width,
+                o.height,
+                s.width,
+                s.height,
+            ),
+        }
+    }
+}
+
+/// The ways a change in the references breaks a pack file.
+pub enum BrokenFile {
+    /// Holds the path of the pack file.
+    Removed(PathBuf),
+
+    /// Holds the path of the pack file, its size and the new vanilla size.
+    Resized(PathBuf, ImageSize, ImageSize),
+
+    /// Holds the location of the entry and its key.
+    RemovedKey(String, String),
+}
+
+impl Display for BrokenFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Removed(p) => write!(f, "{p:?}\t: Its asset was removed from the game."),
+            Self::Resized(p, s, n) => write!(
+                f,
+                "{p:?}\t: Is {}x{}, but the vanilla image is now {}x{}.",
+                s.width,
+                s.height,
+                n.width,
+                n.height,
+            ),
+            Self::RemovedKey(l, k) => write!(f, "{l}\t: The key `{k}` was removed from the game."),
+        }
+    }
+}
+
+/// The contents of a reference dir that can be compared.
+/// Each one is [None] if its file is missing.
+struct RefSet {
+    /// Maps image paths, relative to `Images/`, to their size.
+    images: Option<HashMap<String, Option<ImageSize>>>,
+
+    /// Sound paths, relative to `Sounds/`.
+    sounds: Option<HashSet<String>>,
+
+    /// Song names.
+    music: Option<HashSet<String>>,
+
+    /// Localization keys.
+    loc: Option<HashSet<String>>,
+}
+
+impl RefSet {
+    /// Reads the references in `ref_dir`.
+    fn open(ref_dir: &PathBuf) -> Result<Self, Box<dyn Error>> {
+        let image_path = paths::push(ref_dir, IMAGE_REF_NAME);
+
+        let images = if image_path.is_file() {
+            let slop = Slop::open(image_path)?;
+            check_slop(&slop, &IMAGE_REF);
+
+            let data = images::slop_into_image_data(slop);
+            let images = data
+                .iter()
+                .flat_map(|(dir_key, images)| {
+                    images.iter().map(|i| (images::reference_path(dir_key, &i.file_name), i.size))
+                })
+                .collect();
+
+            Some(images)
+        } else {
+            None
+        };
+
+        let sound_path = paths::push(ref_dir, SOUND_REF_NAME);
+
+        let sounds = if sound_path.is_file() {
+            let slop = Slop::open(sound_path)?;
+            check_slop(&slop, &SOUND_REF);
+
+            let data = sounds::slop_into_sound_data(slop);
+            let sounds = data
+                .iter()
+                .flat_map(|(dir_key, sounds)| {
+                    sounds.keys().map(|s| images::reference_path(dir_key, s))
+                })
+                .collect();
+
+            Some(sounds)
+        } else {
+            None
+        };
+
+        let music_path = paths::push(ref_dir, MUSIC_REF_NAME);
+
+        let music = if music_path.is_file() {
+            Some(music::open_music_ref(music_path)?.into_keys().collect())
+        } else {
+            None
+        };
+
+        let loc_path = paths::push(ref_dir, LOC_REF_NAME);
+        let loc = if loc_path.is_file() { Some(loc::open_loc_ref(loc_path)?) } else { None };
+
+        Ok(Self { images, sounds, music, loc })
+    }
+}
+
+/// The changes in the assets of one kind, or in the localization keys.
+struct Changes {
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl Changes {
+    /// Finds what is in `new` but not `old`, and the other way around.
+    fn between<'a, I>(old: I, new: I) -> Self
+    where
+        I: Iterator<Item = &'a String> + Clone,
+    {
+        let old_set: HashSet<&String> = old.clone().collect();
+        let new_set: HashSet<&String> = new.clone().collect();
+
+        let mut added: Vec<String> = new.filter(|n| !old_set.contains(n)).cloned().collect();
+        let mut removed: Vec<String> = old.filter(|n| !new_set.contains(n)).cloned().collect();
+
+        added.sort();
+        removed.sort();
+
+        Self { added, removed }
+    }
+
+    fn to_ref_changes(&self) -> Vec<RefChange> {
+        self.added
+            .iter()
+            .map(|n| RefChange::Added(n.clone()))
+            .chain(self.removed.iter().map(|n| RefChange::Removed(n.clone())))
+            .collect()
+    }
+}
+
+/// Compares the references in `old_dir` and `new_dir`, and prints the changes.
+///
+/// If `pack_dir` is passed, also lists the pack files the changes break and
+/// the new assets and localization keys the pack doesn't have yet.
+pub fn diff_refs(old_dir: &PathBuf, new_dir: &PathBuf, pack_dir: Option<&PathBuf>)
+    -> Result<(), Box<dyn Error>>
+{
+    for dir in [old_dir, new_dir] {
+        assert!(dir.is_dir(), "expected {dir:?} to be a dir with reference files");
+    }
+
+    output::announce_path("Reading the old references in", old_dir);
+    let old = RefSet::open(old_dir)?;
+
+    output::announce_path("Reading the new references in", new_dir);
+    let new = RefSet::open(new_dir)?;
+
+    let mut broken = vec![];
+    let mut needed_assets = vec![];
+    let mut needed_keys = None;
+
+    if let (Some(o), Some(n)) = (&old.images, &new.images) {
+        let changes = Changes::between(o.keys(), n.keys());
+
+        let mut resized: Vec<(&String, ImageSize, ImageSize)> = o
+            .iter()
+            .filter_map(|(path, size)| match (size, n.get(path)) {
+                (Some(s), Some(Some(t))) if s != t => Some((path, *s, *t)),
+                _ => None,
+            })
+            .collect();
+
+        resized.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut ref_changes = changes.to_ref_changes();
+        ref_changes.extend(resized.iter().map(|(p, s, t)| RefChange::Resized(p.to_string(), *s, *t)));
+
+        print_changes(IMAGE_REF_NAME, &ref_changes);
+
+        if let Some(pack_dir) = pack_dir {
+            let kind = AssetKind::Image;
+            find_broken_assets(pack_dir, kind, &changes, &mut broken, &mut needed_assets);
+
+            for (path, _, new_size) in resized {
+                let Some(file) = AssetKind::Image.find_in_pack(pack_dir, path) else {
+                    continue;
+                };
+
+                match imagesize::size(&file) {
+                    Ok(s) if s == new_size => (),
+                    Ok(s) => broken.push(BrokenFile::Resized(file, s, new_size)),
+                    Err(_) => (),
+                }
+            }
+        }
+    } else {
+        print_skipped(IMAGE_REF_NAME);
+    }
+
+    for (name, kind, old, new) in [
+        (SOUND_REF_NAME, AssetKind::Sound, &old.sounds, &new.sounds),
+        (MUSIC_REF_NAME, AssetKind::Music, &old.music, &new.music),
+    ] {
+        let (Some(o), Some(n)) = (old, new) else {
+            print_skipped(name);
+            continue;
+        };
+
+        let changes = Changes::between(o.iter(), n.iter());
+        print_changes(name, &changes.to_ref_changes());
+
+        if let Some(pack_dir) = pack_dir {
+            find_broken_assets(pack_dir, kind, &changes, &mut broken, &mut needed_assets);
+        }
+    }
+
+    if let (Some(o), Some(n)) = (&old.loc, &new.loc) {
+        let changes = Changes::between(o.iter(), n.iter());
+        print_changes(LOC_REF_NAME, &changes.to_ref_changes());
+
+        if let Some(pack_dir) = pack_dir {
+            needed_keys = find_broken_keys(pack_dir, &changes, &mut broken)?;
+        }
+    } else {
+        print_skipped(LOC_REF_NAME);
+    }
+
+    if let Some(pack_dir) = pack_dir {
+        println!();
+        output::announce_path("Impact on the pack in", pack_dir);
+        output::problem_list(&broken);
+        print_needed(&needed_assets, "asset");
+
+        if let Some(k) = &needed_keys {
+            print_needed(k, "localization key");
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the pack files whose asset was removed, and the new assets the pack
+/// doesn't have.
+fn find_broken_assets(
+    pack_dir: &PathBuf,
+    kind: AssetKind,
+    changes: &Changes,
+    broken: &mut Vec<BrokenFile>,
+    needed: &mut Vec<String>,
+) {
+    for name in &changes.removed {
+        if let Some(file) = kind.find_in_pack(pack_dir, name) {
+            broken.push(BrokenFile::Removed(file));
+        }
+    }
+
+    for name in &changes.added {
+        if kind.find_in_pack(pack_dir, name).is_none() {
+            let path = Path::new(kind.content_dir()).join(kind.file_name(name));
+            needed.push(path.to_str().expect(paths::EXPECT_UTF8_PATH).to_string());
+        }
+    }
+}
+
+/// Finds the pack's entries whose key was removed, and returns the new keys
+/// the pack doesn't define.
+///
+/// Returns [None] if the pack has no localization entries, as it doesn't
+/// translate anything yet.
+fn find_broken_keys(pack_dir: &PathBuf, changes: &Changes, broken: &mut Vec<BrokenFile>)
+    -> Result<Option<Vec<String>>, Box<dyn Error>>
+{
+    let loc_dir = paths::push(pack_dir, "Content/Localization");
+
+    if !loc_dir.is_dir() {
+        return Ok(None);
+    }
+
+    let removed: HashSet<&String> = changes.removed.iter().collect();
+    let mut defined = HashSet::new();
+
+    for entry in WalkDir::new(&loc_dir).max_depth(1) {
+        let path = entry?.path().to_path_buf();
+
+        if path.is_dir() {
+            continue;
+        }
+
+        let Some(file_type) = LocFileType::from_file_name(paths::file_name(&path)) else {
+            continue;
+        };
+
+        // Unreadable files are already reported by `scan`.
+        let Ok(file) = LocFile::open(&path, file_type) else {
+            continue;
+        };
+
+        for entry in file.entries {
+            if removed.contains(&entry.key) {
+                broken.push(BrokenFile::RemovedKey(entry.location(&file.file_name), entry.key.clone()));
+            }
+
+            defined.insert(entry.key);
+        }
+    }
+
+    if defined.is_empty() {
+        return Ok(None);
+    }
+
+    let needed = changes.added
+        .iter()
+        .filter(|k| !defined.contains(*k))
+        .map(|k| format!("`{k}`"))
+        .collect();
+
+    Ok(Some(needed))
+}
+
+/// Prints the changes in the reference file `name`.
+fn print_changes(name: &str, changes: &[RefChange]) {
+    println!();
+    output::announce("Comparing", name);
+
+    let title = match changes.len().cmp(&1) {
+        Ordering::Less => {
+            println!("{} Nothing changed.", *DASH);
+            return;
+        }
+        Ordering::Equal => "Found 1 change:".to_string(),
+        Ordering::Greater => format!("Found {} changes:", changes.len()),
+    };
+
+    output::titled_list(&*DASH, &title, changes);
+}
+
+/// Prints that the reference file `name` is missing from one of the dirs.
+fn print_skipped(name: &str) {
+    println!();
+    output::warn(&format!("`{name}` is missing from one of the dirs, so it wasn't compared."));
+}
+
+/// Prints the new `kind`s (such as assets) the pack doesn't have yet.
+fn print_needed(needed: &[String], kind: &str) {
+    let title = match needed.len().cmp(&1) {
+        Ordering::Less => {
+            println!("{} The pack has every new {kind}!", *DASH);
+            return;
+        }
+        Ordering::Equal => format!("This new {kind} isn't in the pack yet:"),
+        Ordering::Greater => format!("These {} new {kind}s aren't in the pack yet:", needed.len()),
+    };
+
+    output::titled_list(&*RED_DASH, &title, needed);
+}
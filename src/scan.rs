@@ -1,16 +1,38 @@
 //! Handles the scanning and actual 'diagnostic' of a Resource Pack.
 
-use std::{path::PathBuf, error::Error};
+use std::{path::{Path, PathBuf}, error::Error};
 
 use crate::{output, paths};
 
+pub mod case;
+pub mod chat_tags;
+pub mod duplicates;
+pub mod game_version;
+pub mod icon;
 pub mod images;
 pub mod loc;
 pub mod music;
+pub mod pack;
 pub mod sounds;
+pub mod workshop;
 
 const MSG_BAD_REF_DIR: &str = "expected `-r` to be the dir with the reference files";
 
+/// Returns the file next to `path` with the same stem and an extension that
+/// comes before `path`'s in `priority`, if there is one.
+///
+/// When a pack has several formats of the same asset, the game only uses the
+/// one whose extension comes first.
+pub fn preferred_sibling(path: &Path, priority: &[&str]) -> Option<PathBuf> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+
+    priority
+        .iter()
+        .take_while(|e| **e != extension)
+        .map(|e| path.with_extension(e))
+        .find(|p| p.is_file())
+}
+
 pub fn scan_resource_pack(root_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
     output::divider("ACTION - Scan Directory");
 
@@ -18,6 +40,18 @@ pub fn scan_resource_pack(root_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), B
         panic!("{MSG_BAD_REF_DIR}");
     }
 
+    game_version::scan_game_version(root_dir, ref_dir)?;
+    pack::scan_pack_file(root_dir)?;
+    icon::scan_icon(root_dir)?;
+    workshop::scan_workshop_file(root_dir)?;
+    case::scan_path_case(root_dir)?;
+
+    let content_dir = paths::push(root_dir, "Content/");
+
+    if content_dir.is_dir() {
+        duplicates::scan_duplicates(&content_dir)?;
+    }
+
     let images_dir = paths::push(root_dir, "Content/Images/");
 
     if images_dir.is_dir() {
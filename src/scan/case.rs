@@ -0,0 +1,190 @@
+//! Finds paths in a Resource Pack that only work on case-insensitive systems.
+
+use std::{collections::BTreeMap, error::Error, fmt::Display, fs, path::PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::{output, paths::{self, EXPECT_UTF8_PATH}};
+
+/// The directories the game looks into, relative to the root of the pack.
+const EXPECTED_DIRS: [&str; 5] = [
+    "Content",
+    "Content/Images",
+    "Content/Localization",
+    "Content/Music",
+    "Content/Sounds",
+];
+
+/// The possible problems with letter case in the pack's paths.
+pub enum InvalidCase {
+    /// Holds the path of the directory and the name it should have.
+    DirName(PathBuf, String),
+
+    /// Holds the paths that are the same when letter case is ignored.
+    Collision(Vec<PathBuf>),
+}
+
+impl Display for InvalidCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DirName(p, n) => write!(
+                f,
+                "{p:?}\t: Should be named `{n}`. The game won't find it on case-sensitive systems.",
+            ),
+            Self::Collision(p) => {
+                let paths = p.iter().map(|p| format!("{p:?}")).collect::<Vec<_>>();
+                write!(f, "{}\t: Collide when letter case is ignored.", paths.join(", "))
+            }
+        }
+    }
+}
+
+/// Scans the paths of the pack for letter case problems and prints its
+/// findings.
+pub fn scan_path_case(root_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
+    println!();
+    output::announce_path("Scanning letter case in", root_dir);
+
+    let mut problems = find_misnamed_dirs(root_dir)?;
+    problems.extend(find_collisions(root_dir)?);
+
+    output::divider("Scan complete.");
+    output::problem_list(&problems);
+    Ok(())
+}
+
+/// Returns the expected directories that exist, but with the wrong letter case.
+fn find_misnamed_dirs(root_dir: &PathBuf) -> Result<Vec<InvalidCase>, Box<dyn Error>> {
+    let mut problems = vec![];
+
+    for expected in EXPECTED_DIRS {
+        let (parent, name) = match expected.rsplit_once('/') {
+            Some((p, n)) => (paths::push(root_dir, p), n),
+            None => (root_dir.clone(), expected),
+        };
+
+        if !parent.is_dir() {
+            continue;
+        }
+
+        for entry in fs::read_dir(&parent)? {
+            let path = entry?.path();
+            let file_name = paths::file_name(&path);
+
+            if path.is_dir() && file_name != name && file_name.eq_ignore_ascii_case(name) {
+                let relative_path = path
+                    .strip_prefix(root_dir)
+                    .expect("expected path to be a child of the pack")
+                    .to_path_buf();
+
+                problems.push(InvalidCase::DirName(relative_path, expected.to_string()));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Returns the groups of paths under `Content/` that are the same when letter
+/// case is ignored.
+///
+/// Every dir at the root whose name is `Content` in any letter case is walked,
+/// so differently cased copies of it collide too.
+fn find_collisions(root_dir: &PathBuf) -> Result<Vec<InvalidCase>, Box<dyn Error>> {
+    let mut groups: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+    for entry in fs::read_dir(root_dir)? {
+        let content_dir = entry?.path();
+
+        let is_content = paths::file_name(&content_dir).eq_ignore_ascii_case(EXPECTED_DIRS[0]);
+
+        if !is_content || !content_dir.is_dir() {
+            continue;
+        }
+
+        for entry in WalkDir::new(&content_dir) {
+            let entry = entry?;
+
+            let relative_path = entry
+                .path()
+                .strip_prefix(root_dir)
+                .expect("expected path to be a child of the pack")
+                .to_path_buf();
+
+            // Built from the components, so the separator doesn't matter.
+            let key = relative_path
+                .iter()
+                .map(|c| c.to_str().expect(EXPECT_UTF8_PATH).to_lowercase())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            groups.entry(key).or_default().push(relative_path);
+        }
+    }
+
+    let collisions = groups
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .map(InvalidCase::Collision)
+        .collect();
+
+    Ok(collisions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn create_files(root_dir: &Path, files: &[&str]) {
+        for file in files {
+            let path = root_dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+    }
+
+    fn collisions(root_dir: &Path) -> Vec<Vec<PathBuf>> {
+        find_collisions(&root_dir.to_path_buf())
+            .unwrap()
+            .into_iter()
+            .map(|c| match c {
+                InvalidCase::Collision(p) => p,
+                _ => panic!("expected only collisions"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_paths_that_only_differ_in_case() {
+        let dir = tempfile::tempdir().unwrap();
+        create_files(dir.path(), &[
+            "Content/Images/Item_1.png",
+            "Content/Images/item_1.png",
+            "Content/Images/Item_2.png",
+            "Content/Sounds/A.xnb",
+            "content/Sounds/a.xnb",
+        ]);
+
+        let mut found = collisions(dir.path());
+        found.iter_mut().for_each(|g| g.sort());
+
+        let expected: Vec<Vec<PathBuf>> = vec![
+            vec!["Content".into(), "content".into()],
+            vec!["Content/Images/Item_1.png".into(), "Content/Images/item_1.png".into()],
+            vec!["Content/Sounds".into(), "content/Sounds".into()],
+            vec!["Content/Sounds/A.xnb".into(), "content/Sounds/a.xnb".into()],
+        ];
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn ignores_paths_outside_content() {
+        let dir = tempfile::tempdir().unwrap();
+        create_files(dir.path(), &[".git/HEAD", ".git/head", "Content/Images/Item_1.png"]);
+
+        assert!(collisions(dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,19 @@
+//! Holds the patterns of the chat tags the game formats text with, such as
+//! `[c/FF0000:text]` or `[i:23]`.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// Matches the start of a chat tag at the start of the text, such as
+    /// `[c/FF0000:` or `[i:`.
+    ///
+    /// ## Captures
+    ///
+    /// - `1`: Tag name
+    /// - `2`: Tag options, without the leading `/`
+    pub static ref RE_CHAT_TAG_START: Regex = Regex::new(r"^\[([a-z]+)(?:/([^:\]\[]*))?:").unwrap();
+
+    /// Matches the options of a valid `c` tag.
+    pub static ref RE_HEX_COLOR: Regex = Regex::new(r"^[0-9A-Fa-f]{6}$").unwrap();
+}
@@ -0,0 +1,148 @@
+//! Finds files in a Resource Pack that have the exact same contents.
+
+use std::{collections::HashMap, error::Error, ffi::OsStr, fmt::Display, fs, path::PathBuf};
+
+use walkdir::WalkDir;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::{output, paths};
+
+/// Files with the same contents.
+struct DuplicateGroup {
+    /// The size of each file, in bytes.
+    size: u64,
+
+    /// The paths of the files, relative to `Content/`.
+    paths: Vec<PathBuf>,
+}
+
+impl Display for DuplicateGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let paths: Vec<String> = self.paths
+            .iter()
+            .map(|p| format!("`{}`", paths::path_buf_to_key_name(p)))
+            .collect();
+
+        write!(
+            f,
+            "{} identical files of {} bytes, wasting {} bytes: {}",
+            self.paths.len(),
+            self.size,
+            self.wasted_bytes(),
+            paths.join(", "),
+        )
+    }
+}
+
+impl DuplicateGroup {
+    /// The bytes that would be saved by only keeping one of the files.
+    fn wasted_bytes(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// Scans the files in `<pack>/Content/` for identical ones and prints its
+/// findings.
+pub fn scan_duplicates(content_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
+    println!();
+    output::announce_path("Scanning for duplicate files in", content_dir);
+
+    let groups = find_duplicates(content_dir)?;
+
+    output::divider("Scan complete.");
+    print_duplicates(&groups);
+    Ok(())
+}
+
+/// Returns the groups of identical files under `content_dir`, the most
+/// wasteful first.
+///
+/// Files are only hashed if another file has the same size.
+fn find_duplicates(content_dir: &PathBuf) -> Result<Vec<DuplicateGroup>, Box<dyn Error>> {
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+
+    for entry in WalkDir::new(content_dir) {
+        let entry = entry?;
+        let path = entry.path().to_path_buf();
+
+        if path.is_dir() || path.file_name() == Some(OsStr::new("desktop.ini")) {
+            continue;
+        }
+
+        let size = entry.metadata()?.len();
+
+        if size > 0 {
+            by_size.entry(size).or_default().push(path);
+        }
+    }
+
+    let mut groups = vec![];
+
+    for (size, paths) in by_size.into_iter().filter(|(_, p)| p.len() > 1) {
+        let mut by_hash: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+
+        for path in paths {
+            let hash = xxh3_64(&fs::read(&path)?);
+            let relative_path = path
+                .strip_prefix(content_dir)
+                .expect("expected path to be a child of `Content/`")
+                .to_path_buf();
+
+            by_hash.entry(hash).or_default().push(relative_path);
+        }
+
+        for mut paths in by_hash.into_values().filter(|p| p.len() > 1) {
+            paths.sort();
+            groups.push(DuplicateGroup { size, paths });
+        }
+    }
+
+    groups.sort_by(|a, b| b.wasted_bytes().cmp(&a.wasted_bytes()).then(a.paths.cmp(&b.paths)));
+    Ok(groups)
+}
+
+fn print_duplicates(groups: &[DuplicateGroup]) {
+    let wasted: u64 = groups.iter().map(DuplicateGroup::wasted_bytes).sum();
+
+    if wasted > 0 {
+        output::warn(&format!("Identical files waste {wasted} bytes in total."));
+    }
+
+    output::problem_list(groups);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_identical_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_dir = dir.path().to_path_buf();
+
+        for (file, contents) in [
+            ("Images/A.png", "same"),
+            ("Images/Sub/B.png", "same"),
+            ("Sounds/C.xnb", "same"),
+            ("Sounds/D.xnb", "diff"),
+            ("Sounds/E.xnb", "longer"),
+            ("Sounds/Empty1.xnb", ""),
+            ("Sounds/Empty2.xnb", ""),
+            ("Images/desktop.ini", "same"),
+        ] {
+            let path = content_dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let groups = find_duplicates(&content_dir).unwrap();
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].size, 4);
+        assert_eq!(groups[0].wasted_bytes(), 8);
+
+        let expected: Vec<PathBuf> =
+            vec!["Images/A.png".into(), "Images/Sub/B.png".into(), "Sounds/C.xnb".into()];
+        assert_eq!(groups[0].paths, expected);
+    }
+}
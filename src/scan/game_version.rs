@@ -0,0 +1,99 @@
+//! Compares the version of the game the pack targets with the one the
+//! references were generated from.
+
+use std::{error::Error, fmt::Display, path::PathBuf};
+
+use crate::{config::PackConfig, output, refs, static_file_data::CONFIG_FILE_NAME};
+
+/// The ways a reference file can disagree with the game version the pack
+/// targets.
+pub enum GameVersionMismatch {
+    /// Holds the name of the reference file, the game version it was generated
+    /// from and the one the pack targets.
+    Outdated(&'static str, String, String),
+
+    /// Holds the name of the reference file.
+    Unknown(&'static str),
+}
+
+impl Display for GameVersionMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Outdated(n, v, t) => write!(
+                f,
+                "`{n}`\t: Generated from game version {v}, but the pack targets {t}. \
+                Run `gen` again with the assets of {t}.",
+            ),
+            Self::Unknown(n) => write!(
+                f,
+                "`{n}`\t: Doesn't record the game version it was generated from. \
+                Run `gen` again with `--game-version`.",
+            ),
+        }
+    }
+}
+
+/// Compares the game version in the pack's config file with the ones in the
+/// reference files, and prints its findings.
+///
+/// If the pack doesn't declare a version, the references are only checked
+/// against each other.
+pub fn scan_game_version(root_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let target = PackConfig::open(root_dir)?.and_then(|c| c.game_version);
+    let versions = refs::game_versions(ref_dir);
+
+    let Some(target) = target else {
+        let mut known: Vec<&String> = versions.iter().filter_map(|(_, v)| v.as_ref()).collect();
+        known.sort_by_key(|v| normalize_version(v));
+        known.dedup_by_key(|v| normalize_version(v));
+
+        if known.len() > 1 {
+            output::warn(&format!(
+                "The references were generated from different game versions ({}). \
+                Set `game_version` in `{CONFIG_FILE_NAME}` to see which ones are outdated.",
+                known.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(", "),
+            ));
+        }
+
+        return Ok(());
+    };
+
+    println!();
+    output::announce("Comparing the references with game version", &target);
+
+    let normalized_target = normalize_version(&target);
+
+    let problems: Vec<GameVersionMismatch> = versions
+        .into_iter()
+        .filter_map(|(name, version)| match version {
+            Some(v) if normalize_version(&v) == normalized_target => None,
+            Some(v) => Some(GameVersionMismatch::Outdated(name, v, target.clone())),
+            None => Some(GameVersionMismatch::Unknown(name)),
+        })
+        .collect();
+
+    output::divider("Scan complete.");
+    output::problem_list(&problems);
+    Ok(())
+}
+
+/// Returns `version` in a form that can be compared with others, so `v1.4.4.9`
+/// and `1.4.4.9` count as the same version.
+fn normalize_version(version: &str) -> String {
+    let version = version.trim();
+    let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+
+    version.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_versions() {
+        assert_eq!(normalize_version(" v1.4.4.9 "), normalize_version("1.4.4.9"));
+        assert_eq!(normalize_version("V1.4.5-Preview"), "1.4.5-preview");
+        assert_ne!(normalize_version("1.4.4.9"), normalize_version("1.4.4"));
+    }
+}
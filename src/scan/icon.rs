@@ -0,0 +1,95 @@
+//! Validates the `icon.png` file at the root of a Resource Pack.
+
+use std::{error::Error, fmt::Display, fs, path::PathBuf};
+
+use crate::{
+    output,
+    paths,
+    static_file_data::{ICON_FILE_NAME, ICON_MAX_BYTES, ICON_MAX_DIMENSION, PNG_SIGNATURE},
+};
+
+/// The possible problems with the pack's icon.
+pub enum InvalidIcon {
+    Missing,
+    NotPng,
+    Unreadable,
+
+    /// Holds the width and height of the icon.
+    NotSquare(usize, usize),
+
+    /// Holds the width and height of the icon.
+    TooLarge(usize, usize),
+
+    /// Holds the size of the file in bytes.
+    TooHeavy(u64),
+}
+
+impl Display for InvalidIcon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "No `icon.png` found. The pack will show no icon."),
+            Self::NotPng => write!(f, "The icon is not a real PNG file."),
+            Self::Unreadable => write!(f, "Couldn't read the icon's size."),
+            Self::NotSquare(w, h) => {
+                write!(f, "The icon is {w}\u{00D7}{h}, but it will be displayed as a square.")
+            }
+            Self::TooLarge(w, h) => write!(
+                f,
+                "The icon is {w}\u{00D7}{h}. (expected at most \
+                {ICON_MAX_DIMENSION}\u{00D7}{ICON_MAX_DIMENSION})",
+            ),
+            Self::TooHeavy(b) => write!(
+                f,
+                "The icon takes {b} bytes. (the Workshop accepts at most {ICON_MAX_BYTES})",
+            ),
+        }
+    }
+}
+
+/// Scans the `<pack>/icon.png` file and prints its findings.
+pub fn scan_icon(root_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let path = paths::push(root_dir, ICON_FILE_NAME);
+
+    println!();
+    output::announce_path("Scanning", &path);
+
+    let problems = validate_icon(&path)?;
+
+    output::divider("Scan complete.");
+    output::problem_list(&problems);
+    Ok(())
+}
+
+/// Returns every problem found with the icon at `path`.
+pub fn validate_icon(path: &PathBuf) -> Result<Vec<InvalidIcon>, Box<dyn Error>> {
+    if !path.is_file() {
+        return Ok(vec![InvalidIcon::Missing]);
+    }
+
+    let bytes = fs::read(path)?;
+
+    if !bytes.starts_with(&PNG_SIGNATURE) {
+        return Ok(vec![InvalidIcon::NotPng]);
+    }
+
+    let size = match imagesize::blob_size(&bytes) {
+        Ok(s) => s,
+        Err(_) => return Ok(vec![InvalidIcon::Unreadable]),
+    };
+
+    let mut problems = vec![];
+
+    if size.width != size.height {
+        problems.push(InvalidIcon::NotSquare(size.width, size.height));
+    }
+
+    if size.width > ICON_MAX_DIMENSION || size.height > ICON_MAX_DIMENSION {
+        problems.push(InvalidIcon::TooLarge(size.width, size.height));
+    }
+
+    if bytes.len() as u64 > ICON_MAX_BYTES {
+        problems.push(InvalidIcon::TooHeavy(bytes.len() as u64));
+    }
+
+    Ok(problems)
+}
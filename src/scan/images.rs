@@ -1,4 +1,4 @@
-use std::{collections::HashMap, error::Error, ffi::OsString, path::PathBuf};
+use std::{collections::HashMap, error::Error, ffi::OsString, fs, path::PathBuf};
 
 use lazy_static::lazy_static;
 use slop_rs::Slop;
@@ -8,8 +8,11 @@ use crate::{
     image_data::{ImageData, InvalidImage},
     output,
     paths,
+    png_data,
     scanner::{Scanner, ItemStatus},
-    static_file_data::{self, IMAGE_REF_NAME, IMAGE_REF_VERSION},
+    refs,
+    static_file_data::{COUNT_KEY, IMAGE_REF_NAME},
+    suggest::SuggestionIndex,
 };
 
 use super::MSG_BAD_REF_DIR;
@@ -18,6 +21,10 @@ lazy_static! {
     static ref DESKTOP_INI: OsString = OsString::from("desktop.ini");
 }
 
+/// The extensions images can have, in the order the game looks for them.
+/// Only PNG files are accepted, but XNB ones are still looked for.
+pub const EXTENSIONS: [&str; 2] = ["png", "xnb"];
+
 /// Shorthand for the data taken from the `images.slop` file.
 pub type DataMap = HashMap<String, Vec<ImageData>>;
 
@@ -27,24 +34,50 @@ pub fn scan_images(images_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), Box<dy
     let slop = Slop::open(paths::push(ref_dir, IMAGE_REF_NAME))
         .expect(MSG_BAD_REF_DIR);
 
-    static_file_data::validate_slop(&slop, IMAGE_REF_VERSION);
+    refs::check_slop(&slop, &refs::IMAGE_REF);
 
-    let extracted_count: u32 = slop
-        .get("!count")
-        .expect("expected `images.slop` to have a `!count` keyvalue")
-        .parse_into()
-        .expect("expected `!count` kv be a string")
-        .expect("expected `!count` kv to parse into an unsigned 32 bit integer");
+    // Version 0 references don't store the count.
+    let extracted_count: Option<u32> = slop
+        .get(COUNT_KEY)
+        .map(|c| c
+            .parse_into()
+            .expect("expected `!count` kv be a string")
+            .expect("expected `!count` kv to parse into an unsigned 32 bit integer")
+        );
 
     let mut scanner = Scanner::new("images");
     let data = slop_into_image_data(slop);
+    let extracted_count = extracted_count.unwrap_or(data.values().map(|i| i.len() as u32).sum());
+
+    let index = SuggestionIndex::new(data.iter().flat_map(|(dir_key, images)| {
+        images.iter().map(|i| reference_path(dir_key, &i.file_name))
+    }));
 
     println!();
     output::announce_path("Scanning", images_dir);
 
     scanner.scan(
         WalkDir::new(images_dir).into_iter(),
-        |f| validate_entry(f, images_dir, &data),
+        |f| {
+            let status = validate_entry(f, images_dir, &data)?;
+
+            // Suggestions are only looked for while scanning, as they are slow
+            // to find.
+            match status {
+                ItemStatus::Invalid(InvalidImage::BadName(p, _)) => {
+                    let name = &paths::path_buf_to_key_name(&p)[1..];
+
+                    match index.case_matches(name).into_iter().next() {
+                        Some(n) => Ok(ItemStatus::Invalid(InvalidImage::CaseMismatch(p, n))),
+                        None => {
+                            let suggestions = index.suggest(name);
+                            Ok(ItemStatus::Invalid(InvalidImage::BadName(p, suggestions)))
+                        }
+                    }
+                }
+                s => Ok(s),
+            }
+        },
     )?;
 
     println!();
@@ -73,6 +106,14 @@ pub fn slop_into_image_data(slop: Slop) -> DataMap {
     data
 }
 
+/// Returns the path of an image in the reference, relative to `Images/`.
+pub fn reference_path(dir_key: &str, file_name: &str) -> String {
+    match dir_key.trim_start_matches('/') {
+        "" => file_name.to_string(),
+        d => format!("{d}/{file_name}"),
+    }
+}
+
 fn validate_entry(f: &walkdir::Result<DirEntry>, images_dir: &PathBuf, data: &DataMap)
     -> Result<ItemStatus<InvalidImage>, Box<dyn Error>>
 {
@@ -82,8 +123,14 @@ fn validate_entry(f: &walkdir::Result<DirEntry>, images_dir: &PathBuf, data: &Da
         Err(e) => panic!("{e}"),
     };
 
-    let path = entry.path().to_path_buf();
+    validate_file(entry.path().to_path_buf(), images_dir, data)
+}
 
+/// Validates the file at `path`, including whether another format of it is
+/// used instead.
+pub fn validate_file(path: PathBuf, images_dir: &PathBuf, data: &DataMap)
+    -> Result<ItemStatus<InvalidImage>, Box<dyn Error>>
+{
     // First we need to make sure the entry is an image in the first place.
 
     if path.is_dir() || path.file_name() == Some(&DESKTOP_INI) {
@@ -97,18 +144,24 @@ fn validate_entry(f: &walkdir::Result<DirEntry>, images_dir: &PathBuf, data: &Da
 
     let extension = match path.extension() {
         Some(e) => e,
-        None => return Ok(ItemStatus::Invalid(InvalidImage::BadName(relative_path))),
+        None => return Ok(ItemStatus::Invalid(InvalidImage::BadName(relative_path, vec![]))),
     };
 
-    if extension.to_str() != Some("png") {
-        return Ok(ItemStatus::Invalid(InvalidImage::BadName(relative_path)));
+    if let Some(o) = super::preferred_sibling(&path, &EXTENSIONS) {
+        let o = o.strip_prefix(images_dir).expect("expected path to be a child of `Images/`");
+        return Ok(ItemStatus::Invalid(InvalidImage::Overridden(relative_path, o.to_path_buf())));
+    }
+
+    // Wrongly cased extensions are reported later on.
+    if !extension.eq_ignore_ascii_case(EXTENSIONS[0]) {
+        return Ok(ItemStatus::Invalid(InvalidImage::BadName(relative_path, vec![])));
     }
 
     // Now that we know the entry is an image, let's properly validate it.
     validate_image(path, relative_path, images_dir, data)
 }
 
-pub fn validate_image(path: PathBuf, relative_path: PathBuf, images_dir: &PathBuf, data: &DataMap)
+fn validate_image(path: PathBuf, relative_path: PathBuf, images_dir: &PathBuf, data: &DataMap)
     -> Result<ItemStatus<InvalidImage>, Box<dyn Error>>
 {
     let dir = path
@@ -126,18 +179,36 @@ pub fn validate_image(path: PathBuf, relative_path: PathBuf, images_dir: &PathBu
 
     let data = match data.get(&dir_key) {
         Some(d) => d,
-        None => return Ok(ItemStatus::Invalid(InvalidImage::BadName(relative_path))),
+        None => return Ok(ItemStatus::Invalid(InvalidImage::BadName(relative_path, vec![]))),
     };
 
-    for data in data {
-        match data.validate_image(&dir, file_name) {
-            Ok(_) => return Ok(ItemStatus::Valid),
-            Err(InvalidImage::BadName(_)) => continue,
-            Err(InvalidImage::BadSize(_, b_size, g_size)) => {
-                return Ok(ItemStatus::Invalid(InvalidImage::BadSize(relative_path, b_size, g_size)))
-            }
+    let data = match data.iter().find(|d| d.file_name == file_name) {
+        Some(d) => d,
+        None => return Ok(ItemStatus::Invalid(InvalidImage::BadName(relative_path, vec![]))),
+    };
+
+    let bytes = fs::read(&path)?;
+
+    // The size can only be read once the file is known to be a proper PNG.
+    let warning = match png_data::validate_png(&bytes) {
+        Ok(w) => w,
+        Err(b) => return Ok(ItemStatus::Invalid(InvalidImage::BadFormat(relative_path, b))),
+    };
+
+    match data.validate_image(&dir, file_name) {
+        Ok(_) => (),
+        Err(InvalidImage::BadSize(_, b_size, g_size)) => {
+            return Ok(ItemStatus::Invalid(InvalidImage::BadSize(relative_path, b_size, g_size)))
         }
+        Err(b) => return Ok(ItemStatus::Invalid(b)),
     }
 
-    Ok(ItemStatus::Invalid(InvalidImage::BadName(relative_path)))
+    if let Some(c) = data.find_vanilla_copy(&bytes) {
+        return Ok(ItemStatus::Invalid(InvalidImage::VanillaCopy(relative_path, c)));
+    }
+
+    match warning {
+        Some(w) => Ok(ItemStatus::Invalid(InvalidImage::LoadsBadly(relative_path, w))),
+        None => Ok(ItemStatus::Valid),
+    }
 }
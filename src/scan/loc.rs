@@ -1,18 +1,36 @@
-use std::{cmp::Ordering, collections::HashSet, error::Error, fmt::Display, fs, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
 
 use lazy_static::lazy_static;
 use regex::Regex;
 use walkdir::WalkDir;
 
 use crate::{
+    loc_data::{LocValues, LOCALES},
     output::{self, DASH, RED_DASH},
     paths,
     scanner::{ItemStatus, Scanner},
-    static_file_data::LOC_REF_NAME,
+    refs,
+    static_file_data::{LOC_REF_NAME, LOC_VALUES_REF_NAME, MAX_LIST_SIZE},
+    suggest::{self, SuggestionIndex},
+    text_ref::TextRef,
 };
 
+use self::tokens::Tokens;
+
 use super::MSG_BAD_REF_DIR;
 
+mod json;
+mod substitutions;
+mod tokens;
+
 lazy_static! {
     /// ## Captures
     ///
@@ -23,8 +41,14 @@ lazy_static! {
     ).unwrap();
 }
 
+/// Returns the locale tag at the start of the file name, or [None] if the
+/// file name is invalid.
+pub fn locale_of_file(file_name: &str) -> Option<&str> {
+    RE_LOC_FILE_NAME.captures(file_name).map(|c| c.get(1).unwrap().as_str())
+}
+
 /// The possible types of localization files.
-enum LocFileType {
+pub enum LocFileType {
     Csv,
     Json,
 }
@@ -32,9 +56,7 @@ enum LocFileType {
 impl LocFileType {
     /// Returns the [LocFileType] of the file name, or [None] if the type
     /// is invalid.
-    fn from_file_name(file_name: &str) -> Option<Self> {
-        //TODO: Handle different locales.
-
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
         let caps = RE_LOC_FILE_NAME.captures(file_name)?;
 
         match &caps[2] {
@@ -45,14 +67,29 @@ impl LocFileType {
     }
 }
 
-enum InvalidEntry {
+pub enum InvalidEntry {
     /// Returned if an empty record was found.
-    /// Holds the file name.
+    /// Holds the location of the record.
     EmptyRecord(String),
 
     /// Returned if the key was not found in the reference file.
-    /// Holds the file name and the key itself.
-    BadKey(String, String),
+    /// Holds the location of the entry, the key itself and the most similar
+    /// keys in the reference.
+    BadKey(String, String, Vec<String>),
+
+    /// Returned if the value of a key is not a string.
+    /// Holds the location of the entry and the key itself.
+    BadValue(String, String),
+
+    /// Returned if the value doesn't have the same placeholders and chat tags
+    /// as the vanilla value.
+    /// Holds the location of the entry, the key itself, the missing tokens
+    /// and the added tokens.
+    MismatchedTokens(String, String, Vec<String>, Vec<String>),
+
+    /// Returned if the value has a broken placeholder or chat tag.
+    /// Holds the location of the entry, the key itself and the broken token.
+    MangledToken(String, String, String),
 }
 
 impl Display for InvalidEntry {
@@ -61,23 +98,127 @@ impl Display for InvalidEntry {
             Self::EmptyRecord(p) => {
                 write!(f, "{p}\t: Empty record.")
             }
-            Self::BadKey(p, k) => write!(
+            Self::BadKey(p, k, s) => write!(
                 f,
-                "{p}\t: The key `{k}` was not found in the reference file.",
+                "{p}\t: The key `{k}` was not found in the reference file.{}",
+                suggest::did_you_mean(s),
             ),
+            Self::BadValue(p, k) => write!(f, "{p}\t: The value of `{k}` is not a string."),
+            Self::MismatchedTokens(p, k, missing, added) => {
+                let mut changes: Vec<String> = missing
+                    .iter()
+                    .map(|t| format!("`{t}` is missing"))
+                    .collect();
+
+                changes.extend(added.iter().map(|t| format!("`{t}` was added")));
+                write!(f, "{p}\t: Compared to the vanilla `{k}`, {}.", changes.join(", "))
+            }
+            Self::MangledToken(p, k, t) => {
+                write!(f, "{p}\t: The value of `{k}` has a broken placeholder or tag: `{t}`")
+            }
         }
     }
 }
 
+/// A single translation taken from a localization file.
+pub struct LocEntry {
+    pub key: String,
+    pub value: String,
+
+    /// The 1-based line where the entry starts.
+    pub line: u64,
+
+    /// The 1-based column where the entry starts.
+    pub column: u64,
+}
+
+impl LocEntry {
+    /// Returns the location of the entry, as `<file name>:<line>:<column>`.
+    #[inline]
+    pub fn location(&self, file_name: &str) -> String {
+        format!("{file_name}:{}:{}", self.line, self.column)
+    }
+}
+
+/// Where a key was defined, and what value it was given.
+struct KeyDefinition {
+    location: String,
+    file_name: String,
+    value: String,
+}
+
+/// Shorthand for the keys defined by the pack.
+/// Maps each locale to its keys, and each key to where it was defined.
+type Definitions = HashMap<String, HashMap<String, Vec<KeyDefinition>>>;
+
+/// The contents of a localization file.
+pub struct LocFile {
+    pub file_name: String,
+    pub locale: String,
+    pub entries: Vec<LocEntry>,
+
+    /// Entries that can't hold a translation, such as JSON numbers.
+    pub invalid_entries: Vec<InvalidEntry>,
+}
+
+impl LocFile {
+    /// Reads the localization file at `path`.
+    ///
+    /// JSON files are flattened into dotted keys, and members whose name
+    /// starts with `#` are skipped.
+    pub fn open(path: &PathBuf, file_type: LocFileType) -> Result<Self, Box<dyn Error>> {
+        let file_name = paths::file_name(path).to_string();
+        let locale = locale_of_file(&file_name).unwrap_or("").to_string();
+
+        let (entries, invalid_entries) = match file_type {
+            LocFileType::Csv => (read_csv_entries(path)?, vec![]),
+            LocFileType::Json => {
+                let leaves = json::read_leaves(&fs::read_to_string(path)?)
+                    .map_err(|e| format!("{file_name}:{e}"))?;
+
+                let invalid_entries = leaves.non_strings
+                    .into_iter()
+                    .map(|e| InvalidEntry::BadValue(e.location(&file_name), e.key))
+                    .collect();
+
+                (leaves.entries, invalid_entries)
+            }
+        };
+
+        Ok(Self { file_name, locale, entries, invalid_entries })
+    }
+}
+
+fn read_csv_entries(path: &PathBuf) -> Result<Vec<LocEntry>, Box<dyn Error>> {
+    let mut entries = vec![];
+
+    for record in csv::ReaderBuilder::new().flexible(true).from_path(path)?.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |p| p.line());
+
+        entries.push(LocEntry {
+            key: record.get(0).unwrap_or("").to_string(),
+            value: record.get(1).unwrap_or("").to_string(),
+            line,
+            column: 1,
+        });
+    }
+
+    Ok(entries)
+}
+
 pub fn scan_localization_files(loc_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
-    let reference: HashSet<String> = fs::read_to_string(paths::push(ref_dir, LOC_REF_NAME))
-        .expect(MSG_BAD_REF_DIR)
-        .lines()
-        .map(|l| l.to_string())
-        .collect();
+    let reference = open_loc_ref(paths::push(ref_dir, LOC_REF_NAME))
+        .expect(MSG_BAD_REF_DIR);
+
+    let values_path = paths::push(ref_dir, LOC_VALUES_REF_NAME);
+    let values = if values_path.is_file() { Some(LocValues::open(&values_path)?) } else { None };
 
+    let index = SuggestionIndex::new(&reference);
     let mut scanner = Scanner::new("entries");
     let mut invalid_file_names = vec![];
+    let mut unreadable_files = vec![];
+    let mut definitions: Definitions = HashMap::new();
 
     println!();
     output::announce_path("Scanning", loc_dir);
@@ -92,31 +233,42 @@ pub fn scan_localization_files(loc_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(
 
         let file_name = paths::file_name(&path);
 
-        match LocFileType::from_file_name(file_name) {
+        let file_type = match LocFileType::from_file_name(file_name) {
+            Some(t) => t,
             None => {
                 invalid_file_names.push(file_name.to_string());
                 continue;
             }
-            Some(LocFileType::Csv) => scanner.scan(
-                csv::Reader::from_path(path.clone())?.records(),
-                |r| {
-                    let record = match r {
-                        Ok(r) => r,
-                        Err(e) => panic!("{e}"),
-                    };
-
-                    if let Some(key) = record.get(0) {
-                        Ok(validate_entry(file_name, key, &reference))
-                    } else {
-                        Ok(ItemStatus::Invalid(InvalidEntry::EmptyRecord(file_name.to_string())))
-                    }
-                },
-            )?,
-            Some(LocFileType::Json) => {
-                println!();
-                output::warn("JSON translation files are not supported yet.")
+        };
+
+        let file = match LocFile::open(&path, file_type) {
+            Ok(f) => f,
+            Err(e) => {
+                unreadable_files.push(e.to_string());
+                continue;
             }
+        };
+
+        scanner.scan(
+            file.entries.iter(),
+            |e| Ok(validate_entry(&file, e, &reference, &index, values.as_ref())),
+        )?;
+
+        let locale_definitions = definitions.entry(file.locale.clone()).or_default();
+
+        for entry in &file.entries {
+            if entry.key.trim().is_empty() || entry.key.starts_with('#') {
+                continue;
+            }
+
+            locale_definitions.entry(entry.key.clone()).or_default().push(KeyDefinition {
+                location: entry.location(&file.file_name),
+                file_name: file.file_name.clone(),
+                value: entry.value.clone(),
+            });
         }
+
+        scanner.invalid_items.extend(file.invalid_entries);
     }
 
     println!();
@@ -124,19 +276,80 @@ pub fn scan_localization_files(loc_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(
 
     scanner.print_results(reference.len() as u32);
     print_invalid_files(&invalid_file_names);
+    print_unreadable_files(&unreadable_files);
+    print_duplicate_keys(&definitions);
+
+    let bad_substitutions =
+        substitutions::find_bad_substitutions(&definitions, &reference, values.as_ref());
+
+    output::problem_list(&bad_substitutions);
+
+    match values {
+        Some(v) => print_locale_coverage(&v, &definitions),
+        None => output::info(
+            "Run `gen` again to generate `loc_values.csv` and see the coverage per locale.",
+        ),
+    }
+
     Ok(())
 }
 
-fn validate_entry(file_name: &str, key: &str, reference: &HashSet<String>)
-    -> ItemStatus<InvalidEntry>
-{
-    if reference.contains(key) {
-        ItemStatus::Valid
-    } else if key.chars().next() == Some('#') {
-        ItemStatus::Ignored
-    } else {
-        ItemStatus::Invalid(InvalidEntry::BadKey(file_name.to_string(), key.to_string()))
+/// Opens the `loc_keys.txt` file into a [HashSet].
+///
+/// ## Panics
+///
+/// Panics if the reference is newer than this version of the tool.
+#[inline]
+pub fn open_loc_ref<P: AsRef<Path>>(path: P) -> io::Result<HashSet<String>> {
+    let reference = TextRef::open(path)?;
+    reference.validate(&refs::LOC_REF);
+
+    Ok(reference.lines.into_iter().collect())
+}
+
+fn validate_entry(
+    file: &LocFile,
+    entry: &LocEntry,
+    reference: &HashSet<String>,
+    index: &SuggestionIndex,
+    values: Option<&LocValues>,
+) -> ItemStatus<InvalidEntry> {
+    let key = entry.key.as_str();
+    let location = || entry.location(&file.file_name);
+
+    if key.starts_with('#') {
+        return ItemStatus::Ignored;
+    } else if key.trim().is_empty() {
+        return ItemStatus::Invalid(InvalidEntry::EmptyRecord(location()));
+    } else if !reference.contains(key) {
+        let suggestions = index.suggest(key);
+        return ItemStatus::Invalid(InvalidEntry::BadKey(location(), key.to_string(), suggestions));
     }
+
+    let tokens = Tokens::find(&entry.value);
+
+    if let Some(token) = tokens.mangled.first() {
+        let token = token.clone();
+        return ItemStatus::Invalid(InvalidEntry::MangledToken(location(), key.to_string(), token));
+    }
+
+    // Keys missing from the pack's locale are displayed in English.
+    let vanilla_value = values.and_then(|v| v.get(key, &file.locale).or(v.get(key, LOCALES[0])));
+
+    let vanilla_tokens = match vanilla_value {
+        Some(v) => Tokens::find(v),
+        None => return ItemStatus::Valid,
+    };
+
+    let missing = vanilla_tokens.missing_from(&tokens);
+    let added = tokens.missing_from(&vanilla_tokens);
+
+    if missing.is_empty() && added.is_empty() {
+        return ItemStatus::Valid;
+    }
+
+    let key = key.to_string();
+    ItemStatus::Invalid(InvalidEntry::MismatchedTokens(location(), key, missing, added))
 }
 
 fn print_invalid_files(file_names: &Vec<String>) {
@@ -154,3 +367,94 @@ fn print_invalid_files(file_names: &Vec<String>) {
 
     output::bullet_list(format!("  {dash}"), file_names.iter());
 }
+
+fn print_unreadable_files(errors: &[String]) {
+    match errors.len().cmp(&1) {
+        Ordering::Less => return,
+        Ordering::Equal => println!("{} This file could not be read and was skipped:", *RED_DASH),
+        Ordering::Greater => {
+            println!("{} These files could not be read and were skipped:", *RED_DASH)
+        }
+    }
+
+    output::bullet_list(format!("  {}", *RED_DASH), errors.iter());
+}
+
+/// Prints how many of the keys of each locale the pack translates, followed by
+/// the ones it doesn't.
+fn print_locale_coverage(values: &LocValues, definitions: &Definitions) {
+    let mut locales: Vec<&String> = definitions.keys().collect();
+    locales.sort();
+
+    println!();
+    output::divider("Coverage per locale:");
+
+    for locale in locales {
+        // SAFETY: `locale` was taken from the map's keys.
+        let defined = &definitions[locale];
+
+        let mut untranslated: Vec<&String> = values
+            .keys_in(locale)
+            .filter(|k| !defined.contains_key(*k))
+            .collect();
+
+        untranslated.sort();
+
+        let total = values.keys_in(locale).count();
+        let translated = total - untranslated.len();
+        let percent = if total == 0 { 100.0 } else { translated as f32 / total as f32 * 100.0 };
+
+        let dash = if untranslated.is_empty() { DASH.to_string() } else { RED_DASH.to_string() };
+        let title = format!("`{locale}`: {translated}/{total} keys translated. ({percent:.2}%)");
+
+        output::titled_list(&dash, &title, &untranslated);
+    }
+}
+
+/// Prints the keys that were defined more than once in the same locale, along
+/// with where they were defined.
+fn print_duplicate_keys(definitions: &Definitions) {
+    let mut duplicates: Vec<(&String, &String, &Vec<KeyDefinition>)> = definitions
+        .iter()
+        .flat_map(|(l, keys)| keys.iter().map(move |(k, d)| (l, k, d)))
+        .filter(|(_, _, d)| d.len() > 1)
+        .collect();
+
+    let count = duplicates.len();
+    let dash = if count == 0 { DASH.to_string() } else { RED_DASH.to_string() };
+
+    match count.cmp(&1) {
+        Ordering::Less => {
+            println!("{dash} No duplicate keys found!");
+            return;
+        }
+        Ordering::Equal => println!("{dash} This key was defined more than once:"),
+        Ordering::Greater => println!("{dash} These {count} keys were defined more than once:"),
+    }
+
+    duplicates.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+
+    for (locale, key, key_definitions) in duplicates.iter().take(MAX_LIST_SIZE) {
+        let first = &key_definitions[0];
+
+        if key_definitions.iter().all(|d| d.file_name == first.file_name) {
+            println!(
+                "  {dash} `{key}` is defined {} times in `{}`:",
+                key_definitions.len(),
+                first.file_name,
+            );
+        } else {
+            println!("  {dash} `{key}` is defined in multiple `{locale}` files:");
+        }
+
+        output::bullet_list(format!("    {dash}"), key_definitions.iter().map(|d| &d.location));
+
+        if key_definitions.iter().any(|d| d.value != first.value) {
+            println!("    {dash} The values differ, so the load order decides which one wins.");
+        }
+    }
+
+    if count > MAX_LIST_SIZE {
+        println!("  {dash} ... and {} more.", count - MAX_LIST_SIZE);
+    }
+}
@@ -0,0 +1,285 @@
+//! Reads JSON localization files.
+//!
+//! The files are parsed by `serde_json`, the same as during `build`. As it
+//! doesn't keep track of where values are, the members are then looked up in
+//! the text, so problems can point at a line and column.
+
+use std::{error::Error, fmt::Display};
+
+use serde::{
+    de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize,
+    Deserializer,
+};
+
+use super::LocEntry;
+
+/// Returned if the file is not valid JSON.
+#[derive(Debug)]
+pub struct JsonError {
+    pub line: u64,
+    pub column: u64,
+    pub message: String,
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}\t: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for JsonError {}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        let message = e.to_string();
+
+        // The position is already kept separately.
+        let message = match message.rsplit_once(" at line ") {
+            Some((m, _)) => m.to_string(),
+            None => message,
+        };
+
+        Self { line: e.line() as u64, column: e.column() as u64, message }
+    }
+}
+
+/// The leaves of a localization file, flattened into dotted keys.
+pub struct JsonLeaves {
+    /// Leaves holding strings.
+    pub entries: Vec<LocEntry>,
+
+    /// Leaves holding anything other than a string or an object.
+    pub non_strings: Vec<LocEntry>,
+}
+
+/// A JSON value, reduced to what localization files can hold.
+///
+/// Unlike [serde_json::Value], objects keep repeated members, so duplicate
+/// keys can be reported.
+enum Node {
+    Object(Vec<(String, Node)>),
+    String(String),
+    Other,
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+        let mut members = vec![];
+
+        while let Some(member) = map.next_entry()? {
+            members.push(member);
+        }
+
+        Ok(Node::Object(members))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Node, E> {
+        Ok(Node::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Node, E> {
+        Ok(Node::String(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(Node::Other)
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<Node, E> {
+        Ok(Node::Other)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<Node, E> {
+        Ok(Node::Other)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<Node, E> {
+        Ok(Node::Other)
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<Node, E> {
+        Ok(Node::Other)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Node, E> {
+        Ok(Node::Other)
+    }
+}
+
+/// Reads the text of a JSON localization file, flattening nested objects into
+/// dotted keys. (such as `ItemName.CopperShortsword`)
+///
+/// Members whose name starts with `#` are treated as comments and skipped.
+pub fn read_leaves(text: &str) -> Result<JsonLeaves, JsonError> {
+    let text = text.trim_start_matches('\u{FEFF}');
+
+    let members = match serde_json::from_str(text)? {
+        Node::Object(m) => m,
+        _ => {
+            let message = "expected the root of the file to be an object".to_string();
+            return Err(JsonError { line: 1, column: 1, message });
+        }
+    };
+
+    let mut leaves = JsonLeaves { entries: vec![], non_strings: vec![] };
+    let mut finder = KeyFinder { text, offset: 0 };
+
+    flatten("", members, &mut finder, &mut leaves);
+    Ok(leaves)
+}
+
+/// Adds the leaves of the members of an object to `leaves`, in the order they
+/// are written in.
+fn flatten(
+    prefix: &str,
+    members: Vec<(String, Node)>,
+    finder: &mut KeyFinder,
+    leaves: &mut JsonLeaves,
+) {
+    for (name, node) in members {
+        let (line, column) = finder.find(&name);
+
+        // Comments are still looked up, so later members are found after them.
+        if name.starts_with('#') {
+            if let Node::Object(m) = node {
+                flatten("", m, finder, &mut JsonLeaves { entries: vec![], non_strings: vec![] });
+            }
+
+            continue;
+        }
+
+        let key = if prefix.is_empty() { name } else { format!("{prefix}.{name}") };
+
+        match node {
+            Node::Object(m) => flatten(&key, m, finder, leaves),
+            Node::String(value) => leaves.entries.push(LocEntry { key, value, line, column }),
+            Node::Other => {
+                leaves.non_strings.push(LocEntry { key, value: String::new(), line, column });
+            }
+        }
+    }
+}
+
+/// Finds where members are in the text of a valid JSON file.
+///
+/// Members must be looked up in the order they are written in, as each search
+/// starts where the last one ended.
+struct KeyFinder<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> KeyFinder<'a> {
+    /// Returns the 1-based line and column of the next member named `name`.
+    ///
+    /// If it can't be found, such as when the name is written with escapes,
+    /// the position of the last member found is returned instead.
+    fn find(&mut self, name: &str) -> (u64, u64) {
+        // SAFETY: Strings can always be serialized.
+        let quoted = serde_json::to_string(name).unwrap();
+        let mut start = self.offset;
+
+        while let Some(found) = self.text[start..].find(&quoted) {
+            let found = start + found;
+            let end = found + quoted.len();
+            let is_escaped = self.text[..found].ends_with('\\');
+
+            if !is_escaped && self.text[end..].trim_start().starts_with(':') {
+                self.offset = end;
+                return self.position(found);
+            }
+
+            start = found + 1;
+        }
+
+        self.position(self.offset)
+    }
+
+    /// Returns the 1-based line and column of the byte at `offset`.
+    fn position(&self, offset: usize) -> (u64, u64) {
+        let before = &self.text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+
+        (line as u64, column as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(leaves: &[LocEntry]) -> Vec<(&str, u64, u64)> {
+        leaves.iter().map(|e| (e.key.as_str(), e.line, e.column)).collect()
+    }
+
+    #[test]
+    fn reads_nested_keys_with_positions() {
+        let text = concat!(
+            "\u{FEFF}{\n",
+            "  \"ItemName\": {\n",
+            "    \"Gel\": \"Gel\",\n",
+            "    \"Wood\": \"Wood\"\n",
+            "  },\n",
+            "  \"#Note\": \"skipped\",\n",
+            "  \"Count\": 3\n",
+            "}",
+        );
+        let leaves = read_leaves(text).unwrap();
+
+        let expected = [("ItemName.Gel", 3, 5), ("ItemName.Wood", 4, 5)];
+        assert_eq!(positions(&leaves.entries), expected);
+        assert_eq!(leaves.entries[1].value, "Wood");
+        assert_eq!(positions(&leaves.non_strings), [("Count", 7, 3)]);
+    }
+
+    #[test]
+    fn keeps_duplicate_keys() {
+        let leaves = read_leaves("{\"A\": \"1\",\n\"A\": \"2\"}").unwrap();
+        assert_eq!(positions(&leaves.entries), [("A", 1, 2), ("A", 2, 1)]);
+    }
+
+    #[test]
+    fn skips_keys_quoted_inside_values() {
+        let leaves = read_leaves("{\"A\": \"say \\\"B\\\": hi\", \"B\": \"b\"}").unwrap();
+        assert_eq!(positions(&leaves.entries), [("A", 1, 2), ("B", 1, 24)]);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let e = read_leaves("{\n\"A\": \"1\",\n\"B\": \"").err().unwrap();
+        assert_eq!(e.line, 3);
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_json() {
+        for value in ["NaN", "inf", "+1", "'a'"] {
+            assert!(read_leaves(&format!("{{\"A\": {value}}}")).is_err(), "{value} was accepted");
+        }
+
+        // A non-breaking space is whitespace to Rust, but not to JSON.
+        assert!(read_leaves("{\u{A0}\"A\": \"1\"}").is_err());
+    }
+
+    #[test]
+    fn rejects_roots_that_are_not_objects() {
+        assert!(read_leaves("[\"A\"]").is_err());
+    }
+}
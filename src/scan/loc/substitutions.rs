@@ -0,0 +1,227 @@
+//! Checks the `{$Key}` substitutions inside of localization values.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::loc_data::{LocValues, LOCALES};
+
+use super::{tokens::Tokens, Definitions};
+
+/// The possible problems with substitutions.
+pub enum BadSubstitution {
+    /// Returned if the substituted key doesn't exist.
+    /// Holds the location of the entry and the substituted key.
+    MissingTarget(String, String),
+
+    /// Returned if a value ends up substituting itself.
+    /// Holds the locale and the keys that form the cycle, in order.
+    Cycle(String, Vec<String>),
+}
+
+impl Display for BadSubstitution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingTarget(p, k) => {
+                write!(f, "{p}\t: `{{${k}}}` points to a key that doesn't exist.")
+            }
+            Self::Cycle(l, keys) => {
+                let keys: Vec<String> = keys.iter().map(|k| format!("`{k}`")).collect();
+                let arrow = " \u{2192} ";
+                write!(f, "`{l}`\t: Cyclic substitution: {}{arrow}{}", keys.join(arrow), keys[0])
+            }
+        }
+    }
+}
+
+/// Finds every substitution that points to a missing key, and every cycle
+/// of substitutions.
+///
+/// Substituted keys may be defined by the pack itself, in the same locale,
+/// or exist in the reference.
+pub fn find_bad_substitutions(
+    definitions: &Definitions,
+    reference: &HashSet<String>,
+    values: Option<&LocValues>,
+) -> Vec<BadSubstitution> {
+    let mut problems = vec![];
+
+    let mut locales: Vec<&String> = definitions.keys().collect();
+    locales.sort();
+
+    for locale in locales {
+        // SAFETY: `locale` was taken from the map's keys.
+        let keys = &definitions[locale];
+        let mut graph: HashMap<&str, Vec<String>> = HashMap::new();
+
+        for (key, key_definitions) in keys {
+            for definition in key_definitions {
+                let tokens = Tokens::find(&definition.value);
+
+                for target in tokens.substitutions() {
+                    if !reference.contains(target) && !keys.contains_key(target) {
+                        problems.push(BadSubstitution::MissingTarget(
+                            definition.location.clone(),
+                            target.to_string(),
+                        ));
+                    }
+                }
+            }
+
+            // The last definition is the one the game keeps.
+            if let Some(definition) = key_definitions.last() {
+                let targets = Tokens::find(&definition.value)
+                    .substitutions()
+                    .map(String::from)
+                    .collect();
+
+                graph.insert(key, targets);
+            }
+        }
+
+        for cycle in find_cycles(&graph, locale, values) {
+            problems.push(BadSubstitution::Cycle(locale.clone(), cycle));
+        }
+    }
+
+    problems
+}
+
+/// Finds the cycles in the substitution graph of a locale.
+///
+/// Keys the pack doesn't define use their vanilla value.
+fn find_cycles(graph: &HashMap<&str, Vec<String>>, locale: &str, values: Option<&LocValues>)
+    -> Vec<Vec<String>>
+{
+    // The vanilla values are only parsed once, as the search visits keys many
+    // times. Most of them substitute nothing, so they are left out.
+    let mut all_targets: HashMap<&str, Vec<String>> = HashMap::new();
+
+    if let Some(values) = values {
+        for key in values.keys() {
+            let value = values.get(key, locale).or(values.get(key, LOCALES[0]));
+
+            if let Some(v) = value.filter(|v| v.contains("{$")) {
+                let targets = Tokens::find(v).substitutions().map(String::from).collect();
+                all_targets.insert(key, targets);
+            }
+        }
+    }
+
+    all_targets.extend(graph.iter().map(|(k, t)| (*k, t.clone())));
+
+    let targets_of = |key: &str| all_targets.get(key).map_or(&[][..], Vec::as_slice);
+
+    let mut cycles = vec![];
+    let mut found_cycles = HashSet::new();
+    let mut finished = HashSet::new();
+
+    let mut starts: Vec<&&str> = graph.keys().collect();
+    starts.sort();
+
+    for start in starts {
+        let mut path: Vec<String> = vec![];
+        let mut stack = vec![(start.to_string(), 0usize)];
+
+        // Iterative depth-first search, where each item of `stack` holds a key
+        // and the index of the next target to visit.
+        while let Some((key, index)) = stack.pop() {
+            if index == 0 {
+                path.push(key.clone());
+            }
+
+            match targets_of(&key).get(index) {
+                Some(target) => {
+                    stack.push((key, index + 1));
+
+                    if let Some(i) = path.iter().position(|k| k == target) {
+                        let cycle = normalize_cycle(&path[i..]);
+
+                        if found_cycles.insert(cycle.clone()) {
+                            cycles.push(cycle);
+                        }
+                    } else if !finished.contains(target) {
+                        stack.push((target.clone(), 0));
+                    }
+                }
+                None => {
+                    path.pop();
+                    finished.insert(key);
+                }
+            }
+        }
+    }
+
+    cycles
+}
+
+/// Rotates the cycle so it starts at its smallest key, so the same cycle is
+/// only reported once.
+fn normalize_cycle(cycle: &[String]) -> Vec<String> {
+    let start = cycle
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, k)| *k)
+        .map_or(0, |(i, _)| i);
+
+    cycle[start..].iter().chain(cycle[..start].iter()).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::KeyDefinition, *};
+
+    /// The definitions of a single `en-US` file holding `entries`.
+    fn definitions(entries: &[(&str, &str)]) -> Definitions {
+        let keys = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (key, value))| {
+                let definition = KeyDefinition {
+                    location: format!("en-US.csv:{}", i + 1),
+                    file_name: "en-US.csv".to_string(),
+                    value: value.to_string(),
+                };
+
+                (key.to_string(), vec![definition])
+            })
+            .collect();
+
+        HashMap::from([("en-US".to_string(), keys)])
+    }
+
+    #[test]
+    fn finds_cycles() {
+        let definitions = definitions(&[
+            ("B", "{$A}"),
+            ("A", "Hi {$B}"),
+            ("C", "{$A} and {$C}"),
+            ("D", "{$ItemName.Gel}"),
+        ]);
+
+        let reference = HashSet::from(["ItemName.Gel".to_string()]);
+        let problems = find_bad_substitutions(&definitions, &reference, None);
+
+        let cycles: Vec<&Vec<String>> = problems
+            .iter()
+            .map(|p| match p {
+                BadSubstitution::Cycle(_, c) => c,
+                BadSubstitution::MissingTarget(..) => panic!("expected only cycles"),
+            })
+            .collect();
+
+        assert_eq!(cycles, [&vec!["A".to_string(), "B".to_string()], &vec!["C".to_string()]]);
+    }
+
+    #[test]
+    fn finds_missing_targets() {
+        let definitions = definitions(&[("A", "{$Missing}")]);
+        let problems = find_bad_substitutions(&definitions, &HashSet::new(), None);
+
+        assert!(matches!(
+            problems.as_slice(),
+            [BadSubstitution::MissingTarget(l, k)] if l == "en-US.csv:1" && k == "Missing",
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+//! Finds the placeholders and chat tags inside of localization values.
+//!
+//! These are the parts of a value that the game replaces when displaying it,
+//! so a translation should keep the same ones as the vanilla value.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::scan::chat_tags::{RE_CHAT_TAG_START, RE_HEX_COLOR};
+
+lazy_static! {
+    /// Matches the inside of a valid `{...}` placeholder, such as `0`, `0:N0`,
+    /// `$ItemName.Gel` or `PlayerName`.
+    static ref RE_PLACEHOLDER: Regex = Regex::new(
+        r"^(?:[0-9]+(?::[^{}]*)?|\$[A-Za-z0-9_.]+|[A-Za-z_][A-Za-z0-9_.]*)$",
+    ).unwrap();
+
+    /// Matches the inside of placeholders that look like they were meant to
+    /// be one of the above, but were mangled.
+    static ref RE_MANGLED_PLACEHOLDER: Regex = Regex::new(r"^\s*\$|^\s*[0-9]+\s*$|^\s+\S+\s*$")
+        .unwrap();
+}
+
+/// The placeholders and chat tags found in a value.
+#[derive(Default)]
+pub struct Tokens {
+    /// The tokens, written the same way for values in any language.
+    /// (the text inside `[c/...:text]` tags is left out, for example)
+    pub valid: Vec<String>,
+
+    /// Parts of the value that look like broken tokens.
+    pub mangled: Vec<String>,
+}
+
+impl Tokens {
+    /// Finds the tokens of `value`.
+    pub fn find(value: &str) -> Self {
+        let mut tokens = Self::default();
+        let mut rest = value;
+
+        while let Some(start) = rest.find(['{', '[']) {
+            rest = &rest[start..];
+
+            let consumed = if rest.starts_with('{') {
+                tokens.push_placeholder(rest)
+            } else {
+                tokens.push_chat_tag(rest)
+            };
+
+            rest = &rest[consumed..];
+        }
+
+        tokens
+    }
+
+    /// Returns the keys referenced by `{$Key}` substitutions.
+    pub fn substitutions(&self) -> impl Iterator<Item = &str> {
+        self.valid
+            .iter()
+            .filter_map(|t| t.strip_prefix("{$"))
+            .map(|t| t.trim_end_matches('}'))
+    }
+
+    /// Returns the tokens that are in `self` but not in `other`, counting
+    /// repeated tokens.
+    pub fn missing_from(&self, other: &Self) -> Vec<String> {
+        let mut other_tokens: Vec<&String> = other.valid.iter().collect();
+        let mut missing = vec![];
+
+        for token in &self.valid {
+            match other_tokens.iter().position(|t| *t == token) {
+                Some(i) => {
+                    other_tokens.swap_remove(i);
+                }
+                None => missing.push(token.clone()),
+            }
+        }
+
+        missing
+    }
+
+    /// Reads the placeholder at the start of `text`, returning how many bytes
+    /// were read.
+    fn push_placeholder(&mut self, text: &str) -> usize {
+        let end = text[1..].find(['{', '}']).map(|i| i + 1);
+
+        let end = match end {
+            Some(e) if text[e..].starts_with('}') => e,
+            _ => {
+                // Unclosed braces are only reported if they look like placeholders.
+                if text[1..].starts_with(|c: char| c.is_ascii_digit() || c == '$') {
+                    self.mangled.push(shorten(text));
+                }
+
+                return 1;
+            }
+        };
+
+        let inside = &text[1..end];
+
+        if RE_PLACEHOLDER.is_match(inside) {
+            self.valid.push(format!("{{{}}}", inside.split(':').next().unwrap_or(inside)));
+        } else if RE_MANGLED_PLACEHOLDER.is_match(inside) || inside.is_empty() {
+            self.mangled.push(text[..=end].to_string());
+        }
+
+        end + 1
+    }
+
+    /// Reads the chat tag at the start of `text`, returning how many bytes
+    /// were read.
+    fn push_chat_tag(&mut self, text: &str) -> usize {
+        let caps = match RE_CHAT_TAG_START.captures(text) {
+            Some(c) => c,
+            None => return 1,
+        };
+
+        // SAFETY: Group 0 always exists.
+        let start_len = caps.get(0).unwrap().end();
+        let name = &caps[1];
+        let options = caps.get(2).map(|o| o.as_str());
+
+        let end = match text[start_len..].find(']') {
+            Some(e) => start_len + e,
+            None => {
+                self.mangled.push(shorten(text));
+                return start_len;
+            }
+        };
+
+        if name != "c" {
+            self.valid.push(text[..=end].to_string());
+            return end + 1;
+        }
+
+        // The text of color tags is translated, and may hold placeholders,
+        // so only the start of the tag is read.
+        match options {
+            Some(color) if RE_HEX_COLOR.is_match(color) => {
+                self.valid.push(format!("[c/{}:...]", color.to_uppercase()));
+            }
+            _ => self.mangled.push(text[..=end].to_string()),
+        }
+
+        start_len
+    }
+}
+
+/// Cuts `text` down to a size that fits in a report.
+fn shorten(text: &str) -> String {
+    const MAX_CHARS: usize = 16;
+
+    if text.chars().count() <= MAX_CHARS {
+        text.to_string()
+    } else {
+        format!("{}...", text.chars().take(MAX_CHARS).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_placeholders_and_chat_tags() {
+        let value = "{0:N0} coins for {$ItemName.Gel}, [i/s5:23] [c/ff0000:{PlayerName}]";
+        let tokens = Tokens::find(value);
+
+        assert_eq!(tokens.valid, [
+            "{0}",
+            "{$ItemName.Gel}",
+            "[i/s5:23]",
+            "[c/FF0000:...]",
+            "{PlayerName}",
+        ]);
+        assert!(tokens.mangled.is_empty());
+        assert_eq!(tokens.substitutions().collect::<Vec<_>>(), ["ItemName.Gel"]);
+    }
+
+    #[test]
+    fn finds_tokens_missing_from_translations() {
+        let vanilla = Tokens::find("{0} hit {1} for {0}! [i:29] [c/FFFF00:Critical]");
+        let translated = Tokens::find("{1} von {0} getroffen! [c/ffff00:Kritisch]");
+
+        assert_eq!(vanilla.missing_from(&translated), ["{0}", "[i:29]"]);
+        assert!(translated.missing_from(&vanilla).is_empty());
+    }
+
+    #[test]
+    fn finds_mangled_tokens() {
+        let tokens = Tokens::find("{ 0 } {$ItemName.Gel [c/red:Hi] [i:29");
+
+        assert!(tokens.valid.is_empty());
+        assert_eq!(tokens.mangled, ["{ 0 }", "{$ItemName.Gel [...", "[c/red:Hi]", "[i:29"]);
+    }
+}
@@ -1,21 +1,51 @@
 use std::{
-    collections::HashSet, error::Error, ffi::OsStr, fmt::Display, fs, io, path::{Path, PathBuf}
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    error::Error,
+    ffi::OsStr,
+    fmt::Display,
+    fs,
+    io,
+    path::{Path, PathBuf},
 };
 
 use walkdir::{DirEntry, WalkDir};
 
 use crate::{
-    output,
+    music_data::{InvalidMusicData, MusicFormat, MusicInfo},
+    output::{self, DASH, RED_DASH},
     paths::{self, EXPECT_UTF8_PATH},
     scanner::{ItemStatus, Scanner},
+    refs,
     static_file_data::MUSIC_REF_NAME,
+    suggest::SuggestionIndex,
+    text_ref::TextRef,
 };
 
-const EXTENSIONS: [&str; 3] = ["mp3", "ogg", "wav"];
+/// Shorthand for the data taken from the `music.txt` file.
+/// Maps the names of the songs to their titles.
+pub type MusicRef = HashMap<String, Option<String>>;
+
+/// The accepted extensions, in the order the game looks for them.
+pub const EXTENSIONS: [&str; 3] = ["ogg", "wav", "mp3"];
 
 pub enum InvalidMusic {
     BadName(PathBuf),
     BadExtension(PathBuf),
+
+    /// Holds the path and the name in the reference that only differs from it
+    /// in letter case.
+    CaseMismatch(PathBuf, String),
+
+    /// Holds the path and the path of the format the game uses instead.
+    Overridden(PathBuf, PathBuf),
+
+    /// Holds the path and the format the file is really in.
+    WrongContainer(PathBuf, MusicFormat),
+
+    /// Holds the path and what is wrong with the file's contents.
+    BadContents(PathBuf, InvalidMusicData),
 }
 
 impl Display for InvalidMusic {
@@ -25,12 +55,28 @@ impl Display for InvalidMusic {
             Self::BadExtension(p) => {
                 write!(f, "{p:?}\t: Invalid file format. Accepted: {}", EXTENSIONS.join(", "))
             }
+            Self::Overridden(p, o) => write!(
+                f,
+                "{p:?}\t: `{}` is used instead, so this file is ignored.",
+                paths::file_name(o),
+            ),
+            Self::WrongContainer(p, c) => {
+                write!(f, "{p:?}\t: Holds {c} audio, which doesn't match its extension.")
+            }
+            Self::BadContents(p, b) => write!(f, "{p:?}\t: {b}"),
+            Self::CaseMismatch(p, n) => write!(
+                f,
+                "{p:?}\t: Only the letter case differs from `{n}` in the reference. \
+                This breaks on case-sensitive systems.",
+            ),
         }
     }
 }
 
 pub fn scan_music(music_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
     let refs = open_music_ref(paths::push(ref_dir, MUSIC_REF_NAME))?;
+    let index = SuggestionIndex::new(refs.keys());
+    let details = RefCell::new(vec![]);
     let mut scanner = Scanner::new("songs");
 
     println!();
@@ -38,29 +84,106 @@ pub fn scan_music(music_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), Box<dyn
 
     scanner.scan(
         WalkDir::new(music_dir).max_depth(1).into_iter(),
-        |f| validate_entry(f, &refs),
+        |f| validate_entry(f, &refs, &index, &details),
     )?;
 
     println!();
     output::divider("Scan complete.");
     scanner.print_results(refs.len() as u32);
+
+    let details = details.into_inner();
+    print_song_details(&details, &refs);
+    print_missing_songs(&details, &refs);
     Ok(())
 }
 
-/// Opens the `music.txt` file into a [HashSet].
-#[inline]
-pub fn open_music_ref<P: AsRef<Path>>(path: P) -> io::Result<HashSet<String>> {
+/// Returns the name of a song, followed by its title if it has one.
+pub fn display_song(name: &str, refs: &MusicRef) -> String {
+    match refs.get(name) {
+        Some(Some(t)) => format!("{name} ({t})"),
+        _ => name.to_string(),
+    }
+}
+
+/// Returns the name of a song file without its extension.
+fn song_name(file_name: &str) -> &str {
+    file_name.rsplit_once('.').map_or(file_name, |(n, _)| n)
+}
+
+/// Sorts song names by their number, rather than alphabetically.
+fn sort_song_names(names: &mut [&String]) {
+    names.sort_by(|a, b| (a.len(), a).cmp(&(b.len(), b)));
+}
+
+/// Prints the stream info of the valid songs.
+fn print_song_details(details: &[(String, MusicInfo)], refs: &MusicRef) {
+    if details.is_empty() {
+        return;
+    }
+
+    let mut details: Vec<&(String, MusicInfo)> = details.iter().collect();
+    details.sort_by(|a, b| (a.0.len(), &a.0).cmp(&(b.0.len(), &b.0)));
+
+    output::divider("Valid songs:");
+    output::bullet_list(
+        DASH.to_string(),
+        details.iter().map(|(n, i)| format!("{}\t: {i}", display_song(song_name(n), refs))),
+    );
+}
+
+/// Prints the songs in the reference that the pack has no valid file for.
+fn print_missing_songs(details: &[(String, MusicInfo)], refs: &MusicRef) {
+    let found: HashSet<&str> = details.iter().map(|(n, _)| song_name(n)).collect();
+
+    let mut missing: Vec<&String> = refs
+        .keys()
+        .filter(|n| !found.contains(n.as_str()))
+        .collect();
+
+    let title = match missing.len().cmp(&1) {
+        Ordering::Less => {
+            println!("{} Every song in the reference is replaced!", *DASH);
+            return;
+        }
+        Ordering::Equal => "This song isn't replaced:".to_string(),
+        Ordering::Greater => format!("These {} songs aren't replaced:", missing.len()),
+    };
+
+    sort_song_names(&mut missing);
+
+    let missing: Vec<String> = missing.iter().map(|n| display_song(n, refs)).collect();
+    output::titled_list(&*RED_DASH, &title, &missing);
+}
+
+/// Opens the `music.txt` file into a [MusicRef].
+///
+/// Each line holds the name of a song, optionally followed by ` = ` and its
+/// title.
+///
+/// ## Panics
+///
+/// Panics if the reference is newer than this version of the tool.
+pub fn open_music_ref<P: AsRef<Path>>(path: P) -> io::Result<MusicRef> {
+    let reference = TextRef::open(path)?;
+    reference.validate(&refs::MUSIC_REF);
+
     Ok(
-        fs::read_to_string(path)?
-            .lines()
-            .map(String::from)
+        reference.lines
+            .into_iter()
+            .map(|l| match l.split_once('=') {
+                Some((n, t)) => (n.trim().to_string(), Some(t.trim().to_string())),
+                None => (l, None),
+            })
             .collect()
     )
 }
 
-fn validate_entry(f: &walkdir::Result<DirEntry>, refs: &HashSet<String>)
-    -> Result<ItemStatus<InvalidMusic>, Box<dyn Error>>
-{
+fn validate_entry(
+    f: &walkdir::Result<DirEntry>,
+    refs: &MusicRef,
+    index: &SuggestionIndex,
+    details: &RefCell<Vec<(String, MusicInfo)>>,
+) -> Result<ItemStatus<InvalidMusic>, Box<dyn Error>> {
     let entry = match f {
         Ok(e) => e,
         Err(e) => panic!("{e}"),
@@ -72,28 +195,88 @@ fn validate_entry(f: &walkdir::Result<DirEntry>, refs: &HashSet<String>)
         return Ok(ItemStatus::Ignored);
     }
 
-    validate_song(path, refs)
+    let file_name = paths::file_name(&path).to_string();
+
+    match inspect_song(path, refs)? {
+        (ItemStatus::Valid, Some(info)) => {
+            details.borrow_mut().push((file_name, info));
+            Ok(ItemStatus::Valid)
+        }
+        (ItemStatus::Invalid(InvalidMusic::BadName(p) | InvalidMusic::BadExtension(p)), _) => {
+            Ok(ItemStatus::Invalid(find_case_mismatch(p, refs, index)))
+        }
+        (s, _) => Ok(s),
+    }
+}
+
+/// Returns [InvalidMusic::CaseMismatch] if the file name only differs from one
+/// in the reference in letter case, or the plain error otherwise.
+fn find_case_mismatch(path: PathBuf, refs: &MusicRef, index: &SuggestionIndex) -> InvalidMusic {
+    let (stem, extension) = match (path.file_stem(), path.extension()) {
+        (Some(s), Some(e)) => (s.to_str().expect(EXPECT_UTF8_PATH), e.to_str().expect(EXPECT_UTF8_PATH)),
+        _ => return InvalidMusic::BadExtension(path),
+    };
+
+    let extension = extension.to_lowercase();
+
+    if !EXTENSIONS.contains(&extension.as_str()) {
+        return InvalidMusic::BadExtension(path);
+    }
+
+    let expected_stem = if refs.contains_key(stem) {
+        stem.to_string()
+    } else {
+        match index.case_matches(stem).into_iter().next() {
+            Some(s) => s,
+            None => return InvalidMusic::BadName(path),
+        }
+    };
+
+    InvalidMusic::CaseMismatch(path, format!("{expected_stem}.{extension}"))
+}
+
+pub fn validate_song(path: PathBuf, refs: &MusicRef) -> Result<ItemStatus<InvalidMusic>, Box<dyn Error>> {
+    inspect_song(path, refs).map(|(s, _)| s)
 }
 
-pub fn validate_song(path: PathBuf, refs: &HashSet<String>) -> Result<ItemStatus<InvalidMusic>, Box<dyn Error>> {
+/// Validates a song, returning its stream info if it is valid.
+fn inspect_song(path: PathBuf, refs: &MusicRef)
+    -> Result<(ItemStatus<InvalidMusic>, Option<MusicInfo>), Box<dyn Error>>
+{
     let extension = match path.extension() {
         Some(e) => e,
-        None => return Ok(ItemStatus::Invalid(InvalidMusic::BadExtension(path))),
+        None => return Ok((ItemStatus::Invalid(InvalidMusic::BadExtension(path)), None)),
     };
 
     let extension = extension.to_str().expect(EXPECT_UTF8_PATH);
 
     if !EXTENSIONS.iter().any(|e| e == &extension) {
-        return Ok(ItemStatus::Invalid(InvalidMusic::BadExtension(path)));
+        return Ok((ItemStatus::Invalid(InvalidMusic::BadExtension(path)), None));
     }
 
     let file_name = paths::file_name(&path);
     // SAFETY: `extension` is created from the same path as `file_name`.
     let file_name = file_name.strip_suffix(&format!(".{extension}")).unwrap();
 
-    if refs.contains(file_name) {
-        Ok(ItemStatus::Valid)
-    } else {
-        Ok(ItemStatus::Invalid(InvalidMusic::BadName(path)))
+    if !refs.contains_key(file_name) {
+        return Ok((ItemStatus::Invalid(InvalidMusic::BadName(path)), None));
+    }
+
+    if let Some(o) = super::preferred_sibling(&path, &EXTENSIONS) {
+        return Ok((ItemStatus::Invalid(InvalidMusic::Overridden(path, o)), None));
+    }
+
+    let bytes = fs::read(&path)?;
+
+    match MusicFormat::sniff(&bytes) {
+        Some(f) if Some(f) != MusicFormat::from_extension(extension) => {
+            return Ok((ItemStatus::Invalid(InvalidMusic::WrongContainer(path, f)), None));
+        }
+        _ => (),
+    }
+
+    match MusicInfo::read(&bytes) {
+        Ok(i) => Ok((ItemStatus::Valid, Some(i))),
+        Err(b) => Ok((ItemStatus::Invalid(InvalidMusic::BadContents(path, b)), None)),
     }
 }
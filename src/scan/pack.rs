@@ -0,0 +1,198 @@
+//! Validates the `pack.json` file at the root of a Resource Pack.
+
+use std::{error::Error, fmt::Display, fs, path::PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    output,
+    paths,
+    scan::chat_tags::{RE_CHAT_TAG_START, RE_HEX_COLOR},
+    static_file_data::PACK_FILE_NAME,
+};
+
+/// The fields the game needs to list the pack.
+const REQUIRED_FIELDS: [&str; 4] = ["Name", "Author", "Description", "Version"];
+
+/// The fields inside of the `Version` object.
+const VERSION_FIELDS: [&str; 2] = ["major", "minor"];
+
+/// The possible problems with the fields of `pack.json`.
+pub enum InvalidPackField {
+    /// Holds the name of the missing field.
+    Missing(String),
+
+    /// Holds the name of the field and the expected type.
+    WrongType(String, &'static str),
+
+    /// Holds the name of the empty field.
+    Empty(String),
+
+    /// Holds the name of the field and what is wrong with it.
+    BadFormatting(String, String),
+
+    /// Holds the name of the field the game doesn't know about.
+    Unknown(String),
+}
+
+impl Display for InvalidPackField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(n) => write!(f, "`{n}`\t: Required field is missing."),
+            Self::WrongType(n, t) => write!(f, "`{n}`\t: Expected {t}."),
+            Self::Empty(n) => write!(f, "`{n}`\t: Field is empty."),
+            Self::BadFormatting(n, r) => write!(f, "`{n}`\t: {r}"),
+            Self::Unknown(n) => write!(f, "`{n}`\t: Unknown field. The game will ignore it."),
+        }
+    }
+}
+
+/// Scans the `<pack>/pack.json` file and prints its findings.
+pub fn scan_pack_file(root_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let path = paths::push(root_dir, PACK_FILE_NAME);
+
+    println!();
+    output::announce_path("Scanning", &path);
+
+    if !path.is_file() {
+        output::warn("No `pack.json` found. The game will not list the pack.");
+        return Ok(());
+    }
+
+    let object = match parse_pack_file(&fs::read_to_string(&path)?) {
+        Ok(o) => o,
+        Err(e) => {
+            output::warn(&format!("`pack.json` is malformed: {e}"));
+            output::warn("The game will not list the pack.");
+            return Ok(());
+        }
+    };
+
+    output::divider("Scan complete.");
+    output::problem_list(&validate_fields(&object));
+    Ok(())
+}
+
+/// Returns every problem found in the contents of a `pack.json` file.
+///
+/// Fails if the contents aren't a well-formed JSON object.
+pub fn validate_pack_file(text: &str) -> Result<Vec<InvalidPackField>, Box<dyn Error>> {
+    Ok(validate_fields(&parse_pack_file(text)?))
+}
+
+/// Returns every problem found in the fields of `object`.
+fn validate_fields(object: &Map<String, Value>) -> Vec<InvalidPackField> {
+    field_names(object)
+        .iter()
+        .filter_map(|n| validate_field(n, object).err())
+        .collect()
+}
+
+fn parse_pack_file(text: &str) -> Result<Map<String, Value>, Box<dyn Error>> {
+    match serde_json::from_str(text.trim_start_matches('\u{FEFF}'))? {
+        Value::Object(o) => Ok(o),
+        _ => Err("expected the root of the file to be an object".into()),
+    }
+}
+
+/// Returns the required field names, followed by any other field in `object`.
+fn field_names(object: &Map<String, Value>) -> Vec<String> {
+    let others = object
+        .keys()
+        .filter(|k| !REQUIRED_FIELDS.contains(&k.as_str()))
+        .cloned();
+
+    REQUIRED_FIELDS.iter().map(|n| n.to_string()).chain(others).collect()
+}
+
+fn validate_field(name: &str, object: &Map<String, Value>) -> Result<(), InvalidPackField> {
+    let value = match object.get(name) {
+        Some(v) => v,
+        None if REQUIRED_FIELDS.contains(&name) => {
+            return Err(InvalidPackField::Missing(name.to_string()));
+        }
+        None => return Ok(()),
+    };
+
+    match name {
+        "Name" | "Author" => validate_text(name, value, false),
+        "Description" => validate_text(name, value, true),
+        "Version" => validate_version(value),
+        _ => Err(InvalidPackField::Unknown(name.to_string())),
+    }
+}
+
+fn validate_text(name: &str, value: &Value, multiline: bool) -> Result<(), InvalidPackField> {
+    let text = value
+        .as_str()
+        .ok_or(InvalidPackField::WrongType(name.to_string(), "a string"))?;
+
+    if text.trim().is_empty() {
+        return Err(InvalidPackField::Empty(name.to_string()));
+    }
+
+    if !multiline && text.contains('\n') {
+        let reason = "Line breaks are not allowed here.".to_string();
+        return Err(InvalidPackField::BadFormatting(name.to_string(), reason));
+    }
+
+    if text.chars().any(|c| c.is_control() && c != '\n') {
+        let reason = "Contains control characters.".to_string();
+        return Err(InvalidPackField::BadFormatting(name.to_string(), reason));
+    }
+
+    match find_bad_chat_tag(text) {
+        Some(r) => Err(InvalidPackField::BadFormatting(name.to_string(), r)),
+        None => Ok(()),
+    }
+}
+
+fn validate_version(value: &Value) -> Result<(), InvalidPackField> {
+    let object = value
+        .as_object()
+        .ok_or(InvalidPackField::WrongType("Version".to_string(), "an object"))?;
+
+    for field in VERSION_FIELDS {
+        let field_value = object
+            .get(field)
+            .ok_or(InvalidPackField::Missing(format!("Version.{field}")))?;
+
+        if !field_value.is_u64() {
+            return Err(
+                InvalidPackField::WrongType(format!("Version.{field}"), "a positive integer"),
+            );
+        }
+    }
+
+    match object.keys().find(|k| !VERSION_FIELDS.contains(&k.as_str())) {
+        Some(k) => Err(InvalidPackField::Unknown(format!("Version.{k}"))),
+        None => Ok(()),
+    }
+}
+
+/// Returns why the first broken chat tag of `text` is broken, if there is one.
+fn find_bad_chat_tag(text: &str) -> Option<String> {
+    for (start, _) in text.match_indices('[') {
+        let caps = match RE_CHAT_TAG_START.captures(&text[start..]) {
+            Some(c) => c,
+            None => continue,
+        };
+
+        // SAFETY: Group 0 always exists.
+        let tag = caps.get(0).unwrap();
+
+        if !text[start + tag.end()..].contains(']') {
+            return Some(format!("The tag `{}` is never closed.", tag.as_str()));
+        }
+
+        if &caps[1] == "c" {
+            let color = caps.get(2).map_or("", |c| c.as_str());
+
+            if !RE_HEX_COLOR.is_match(color) {
+                return Some(format!("`{color}` is not a valid hex color."));
+            }
+        }
+    }
+
+    None
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet}, error::Error, ffi::OsStr, fmt::Display, path::PathBuf
+    collections::HashMap, error::Error, ffi::OsStr, fmt::Display, fs, path::{Path, PathBuf}
 };
 
 use slop_rs::Slop;
@@ -9,17 +9,37 @@ use crate::{
     output,
     paths,
     scanner::{ItemStatus, Scanner},
-    static_file_data::{self, SOUND_REF_NAME, SOUND_REF_VERSION},
+    refs,
+    static_file_data::{COUNT_KEY, SOUND_REF_NAME},
+    sound_data::{SoundData, SoundFormat, SoundFormatWarning},
+    suggest::SuggestionIndex,
+    wav::WavData,
+    xnb::{InvalidXnb, XnbHeader, SOUND_EFFECT_READER},
 };
 
 use super::MSG_BAD_REF_DIR;
 
+/// The extensions sounds can have, in the order the game looks for them.
+/// WAV files are only used by `build --convert-sounds`.
+pub const EXTENSIONS: [&str; 2] = ["xnb", "wav"];
+
 /// Shorthand for the data taken from the `sounds.slop` file.
-type DataMap = HashMap<String, HashSet<String>>;
+/// Maps dir keys to the sounds in them and their vanilla format.
+pub type DataMap = HashMap<String, HashMap<String, Option<SoundFormat>>>;
 
 pub enum InvalidSound {
     BadName(PathBuf),
     BadExtension(PathBuf),
+
+    /// Holds the path and the name in the reference that only differs from it
+    /// in letter case.
+    CaseMismatch(PathBuf, String),
+
+    /// Holds the path and the path of the format the game uses instead.
+    Overridden(PathBuf, PathBuf),
+
+    /// Holds the path and what is wrong with the file's header.
+    BadFormat(PathBuf, InvalidXnb),
 }
 
 impl Display for InvalidSound {
@@ -29,6 +49,17 @@ impl Display for InvalidSound {
             Self::BadExtension(p) => {
                 write!(f, "{p:?}\t: Sound files must be in the XNB format.")
             }
+            Self::BadFormat(p, b) => write!(f, "{p:?}\t: {b}"),
+            Self::Overridden(p, o) => write!(
+                f,
+                "{p:?}\t: `{}` is used instead, so this file is ignored.",
+                paths::file_name(o),
+            ),
+            Self::CaseMismatch(p, n) => write!(
+                f,
+                "{p:?}\t: Only the letter case differs from `{n}` in the reference. \
+                This breaks on case-sensitive systems.",
+            ),
         }
     }
 }
@@ -37,10 +68,10 @@ pub fn scan_sounds(sounds_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), Box<dy
     let slop = Slop::open(paths::push(ref_dir, SOUND_REF_NAME))
         .expect(MSG_BAD_REF_DIR);
 
-    static_file_data::validate_slop(&slop, SOUND_REF_VERSION);
+    refs::check_slop(&slop, &refs::SOUND_REF);
 
     let extracted_count: u32 = slop
-        .get_string("!count")
+        .get_string(COUNT_KEY)
         .expect("expected `images.slop` to have a `!count` keyvalue")
         .parse()
         .expect("expected `!count` kv to parse into an unsigned 32 bit integer");
@@ -48,20 +79,94 @@ pub fn scan_sounds(sounds_dir: &PathBuf, ref_dir: &PathBuf) -> Result<(), Box<dy
     let mut scanner = Scanner::new("sounds");
     let data = slop_into_sound_data(slop);
 
+    let index = SuggestionIndex::new(data.iter().flat_map(|(dir_key, sounds)| {
+        sounds.keys().map(|s| match dir_key.trim_start_matches('/') {
+            "" => s.clone(),
+            d => format!("{d}/{s}"),
+        })
+    }));
+
     println!();
     output::announce_path("Scanning", sounds_dir);
 
     scanner.scan(
         WalkDir::new(sounds_dir).into_iter(),
-        |f| validate_entry(f, sounds_dir, &data),
+        |f| match validate_entry(f, sounds_dir, &data)? {
+            ItemStatus::Invalid(InvalidSound::BadName(p)) => {
+                let name = &paths::path_buf_to_key_name(&p)[1..];
+
+                match index.case_matches(name).into_iter().next() {
+                    Some(n) => Ok(ItemStatus::Invalid(InvalidSound::CaseMismatch(p, n))),
+                    None => Ok(ItemStatus::Invalid(InvalidSound::BadName(p))),
+                }
+            }
+            s => Ok(s),
+        },
     )?;
 
     println!();
     output::divider("Scan complete.");
     scanner.print_results(extracted_count);
+
+    output::divider("Comparing sound formats with vanilla...");
+    output::problem_list(&compare_sound_formats(sounds_dir, &data)?);
     Ok(())
 }
 
+/// Compares the format of every XNB and WAV sound in the reference with its
+/// vanilla counterpart.
+/// Sounds whose format can't be read are skipped, as they are reported by the
+/// scan itself.
+fn compare_sound_formats(sounds_dir: &PathBuf, data: &DataMap)
+    -> Result<Vec<SoundFormatWarning>, Box<dyn Error>>
+{
+    let mut warnings = vec![];
+
+    for entry in WalkDir::new(sounds_dir) {
+        let path = entry?.path().to_path_buf();
+
+        let extension = match path.extension().and_then(OsStr::to_str) {
+            Some(e) if path.is_file() => e.to_lowercase(),
+            _ => continue,
+        };
+
+        let relative_path = path
+            .strip_prefix(sounds_dir)
+            .expect("expected path to be a child of `Sounds/`")
+            .to_path_buf();
+
+        let xnb_path = relative_path.with_extension("xnb");
+        let dir_key = paths::path_buf_to_key_name(
+            &xnb_path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        );
+
+        let vanilla = data
+            .get(&dir_key)
+            .and_then(|d| d.get(paths::file_name(&xnb_path)))
+            .copied()
+            .flatten();
+
+        let vanilla = match vanilla {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let bytes = fs::read(&path)?;
+
+        let format = match extension.as_str() {
+            "xnb" => XnbHeader::parse(&bytes).ok().and_then(|h| h.sound_effect_format(&bytes)),
+            "wav" => WavData::parse(&bytes).ok().map(|w| SoundFormat::from(&w)),
+            _ => None,
+        };
+
+        if let Some(f) = format {
+            warnings.extend(f.compare(&vanilla, &relative_path));
+        }
+    }
+
+    Ok(warnings)
+}
+
 pub fn slop_into_sound_data(slop: Slop) -> DataMap {
     let mut data = HashMap::new();
 
@@ -71,12 +176,32 @@ pub fn slop_into_sound_data(slop: Slop) -> DataMap {
         }
 
         let values = value.list().expect("expected a list kv");
-        data.insert(key, values.iter().map(String::from).collect());
+        let values = values.iter().map(|s| {
+            let sound: SoundData = s.parse().expect("expected a valid sound data string");
+            (sound.file_name, sound.format)
+        });
+
+        data.insert(key, values.collect());
     }
 
     data
 }
 
+/// Returns `true` if `relative_path`, relative to `Sounds/`, is in the
+/// reference.
+pub fn reference_contains(data: &DataMap, relative_path: &Path) -> bool {
+    let dir_key = paths::path_buf_to_key_name(
+        &relative_path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    );
+
+    let file_name = relative_path.file_name().and_then(|f| f.to_str());
+
+    match (data.get(&dir_key), file_name) {
+        (Some(d), Some(f)) => d.contains_key(f),
+        _ => false,
+    }
+}
+
 fn validate_entry(f: &walkdir::Result<DirEntry>, sounds_dir: &PathBuf, data: &DataMap)
     -> Result<ItemStatus<InvalidSound>, Box<dyn Error>>
 {
@@ -85,8 +210,14 @@ fn validate_entry(f: &walkdir::Result<DirEntry>, sounds_dir: &PathBuf, data: &Da
         Err(e) => panic!("{e}"),
     };
 
-    let path = entry.path().to_path_buf();
+    validate_file(entry.path().to_path_buf(), sounds_dir, data)
+}
 
+/// Validates the file at `path`, including whether another format of it is
+/// used instead.
+pub fn validate_file(path: PathBuf, sounds_dir: &PathBuf, data: &DataMap)
+    -> Result<ItemStatus<InvalidSound>, Box<dyn Error>>
+{
     if path.is_dir() || path.file_name() == Some(OsStr::new("desktop.ini")) {
         return Ok(ItemStatus::Ignored);
     }
@@ -101,14 +232,20 @@ fn validate_entry(f: &walkdir::Result<DirEntry>, sounds_dir: &PathBuf, data: &Da
         None => return Ok(ItemStatus::Invalid(InvalidSound::BadName(relative_path))),
     };
 
-    if extension.to_str() != Some("xnb") {
+    if let Some(o) = super::preferred_sibling(&path, &EXTENSIONS) {
+        let o = o.strip_prefix(sounds_dir).expect("expected path to be a child of `Sounds/`");
+        return Ok(ItemStatus::Invalid(InvalidSound::Overridden(relative_path, o.to_path_buf())));
+    }
+
+    // Wrongly cased extensions are reported later on.
+    if !extension.eq_ignore_ascii_case(EXTENSIONS[0]) {
         return Ok(ItemStatus::Invalid(InvalidSound::BadExtension(relative_path)));
     }
 
     validate_sound(path, relative_path, sounds_dir, data)
 }
 
-pub fn validate_sound(path: PathBuf, relative_path: PathBuf, sounds_dir: &PathBuf, data: &DataMap)
+fn validate_sound(path: PathBuf, relative_path: PathBuf, sounds_dir: &PathBuf, data: &DataMap)
     -> Result<ItemStatus<InvalidSound>, Box<dyn Error>>
 {
     let dir = path
@@ -129,9 +266,15 @@ pub fn validate_sound(path: PathBuf, relative_path: PathBuf, sounds_dir: &PathBu
         None => return Ok(ItemStatus::Invalid(InvalidSound::BadName(relative_path))),
     };
 
-    if data.contains(file_name) {
-        Ok(ItemStatus::Valid)
-    } else {
-        Ok(ItemStatus::Invalid(InvalidSound::BadName(relative_path)))
+    if !data.contains_key(file_name) {
+        return Ok(ItemStatus::Invalid(InvalidSound::BadName(relative_path)));
+    }
+
+    let result = XnbHeader::parse(&fs::read(&path)?)
+        .and_then(|h| h.expect_reader(SOUND_EFFECT_READER));
+
+    match result {
+        Ok(_) => Ok(ItemStatus::Valid),
+        Err(b) => Ok(ItemStatus::Invalid(InvalidSound::BadFormat(relative_path, b))),
     }
 }
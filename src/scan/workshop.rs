@@ -0,0 +1,224 @@
+//! Validates the `workshop.json` file the game creates when publishing a
+//! Resource Pack to the Steam Workshop.
+//!
+//! Everything is checked offline; no service is ever contacted.
+
+use std::{collections::HashSet, error::Error, fmt::Display, fs, path::PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    output,
+    paths,
+    static_file_data::{ICON_MAX_BYTES, WORKSHOP_FILE_NAME},
+};
+
+/// The visibility names the game writes, in the same order as their ids.
+const VISIBILITIES: [&str; 3] = ["Public", "FriendsOnly", "Private"];
+
+/// The tags the game offers for Resource Packs when publishing them.
+/// The Workshop drops any other tag.
+const RESOURCE_PACK_TAGS: [&str; 7] =
+    ["Music", "Sounds", "Texture Pack", "Language", "Overhaul", "Vanilla", "Theme"];
+
+/// The possible problems with `workshop.json`.
+pub enum InvalidWorkshopField {
+    /// Holds the name of the field and the expected type.
+    WrongType(&'static str, &'static str),
+
+    /// Holds the content type found in the file.
+    BadContentType(String),
+
+    /// Holds the published id.
+    BadPublishedId(u64),
+
+    MissingPublishedId,
+
+    EmptyTag,
+
+    /// Holds the tag the Workshop doesn't offer.
+    UnknownTag(String),
+
+    /// Holds the repeated tag.
+    DuplicateTag(String),
+
+    /// Holds the visibility found in the file.
+    BadVisibility(String),
+
+    /// Holds the path to the preview image.
+    MissingPreview(String),
+
+    /// Holds the path to the preview image and its size in bytes.
+    HeavyPreview(String, u64),
+}
+
+impl Display for InvalidWorkshopField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongType(n, t) => write!(f, "`{n}`\t: Expected {t}."),
+            Self::BadContentType(t) => {
+                write!(f, "`ContentType`\t: Expected `ResourcePack`, but got `{t}`.")
+            }
+            Self::BadPublishedId(i) => write!(f, "`SteamEntryId`\t: `{i}` is not a valid id."),
+            Self::MissingPublishedId => write!(
+                f,
+                "`SteamEntryId`\t: Missing, so the game will publish the pack as a new item.",
+            ),
+            Self::EmptyTag => write!(f, "`Tags`\t: Contains an empty tag."),
+            Self::UnknownTag(t) => write!(
+                f,
+                "`Tags`\t: `{t}` is not one of {}. The Workshop will drop it.",
+                RESOURCE_PACK_TAGS.join(", "),
+            ),
+            Self::DuplicateTag(t) => write!(f, "`Tags`\t: The tag `{t}` is repeated."),
+            Self::BadVisibility(v) => write!(
+                f,
+                "`Visibility`\t: `{v}` is not one of {}.",
+                VISIBILITIES.join(", "),
+            ),
+            Self::MissingPreview(p) => {
+                write!(f, "`PreviewImagePath`\t: The file {p:?} does not exist.")
+            }
+            Self::HeavyPreview(p, b) => write!(
+                f,
+                "`PreviewImagePath`\t: {p:?} takes {b} bytes. \
+                (the Workshop accepts at most {ICON_MAX_BYTES})",
+            ),
+        }
+    }
+}
+
+/// Scans the `<pack>/workshop.json` file, if there is one, and prints
+/// its findings.
+pub fn scan_workshop_file(root_dir: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let path = paths::push(root_dir, WORKSHOP_FILE_NAME);
+
+    if !path.is_file() {
+        return Ok(());
+    }
+
+    println!();
+    output::announce_path("Scanning", &path);
+
+    let text = fs::read_to_string(&path)?;
+
+    let object = match serde_json::from_str(text.trim_start_matches('\u{FEFF}')) {
+        Ok(Value::Object(o)) => o,
+        Ok(_) => {
+            output::warn("`workshop.json` is malformed: expected the root to be an object");
+            return Ok(());
+        }
+        Err(e) => {
+            output::warn(&format!("`workshop.json` is malformed: {e}"));
+            return Ok(());
+        }
+    };
+
+    let problems = validate_workshop_file(&object, root_dir);
+
+    output::divider("Scan complete.");
+    output::problem_list(&problems);
+    Ok(())
+}
+
+fn validate_workshop_file(object: &Map<String, Value>, root_dir: &PathBuf)
+    -> Vec<InvalidWorkshopField>
+{
+    let mut problems = vec![];
+
+    if let Some(value) = object.get("ContentType") {
+        match value.as_str() {
+            Some("ResourcePack") => (),
+            Some(t) => problems.push(InvalidWorkshopField::BadContentType(t.to_string())),
+            None => problems.push(InvalidWorkshopField::WrongType("ContentType", "a string")),
+        }
+    }
+
+    match object.get("SteamEntryId").map(Value::as_u64) {
+        Some(Some(0)) => problems.push(InvalidWorkshopField::BadPublishedId(0)),
+        Some(Some(_)) => (),
+        Some(None) => problems.push(
+            InvalidWorkshopField::WrongType("SteamEntryId", "a positive integer"),
+        ),
+        None => problems.push(InvalidWorkshopField::MissingPublishedId),
+    }
+
+    if let Some(value) = object.get("Tags") {
+        validate_tags(value, &mut problems);
+    }
+
+    if let Some(value) = object.get("Visibility") {
+        validate_visibility(value, &mut problems);
+    }
+
+    if let Some(value) = object.get("PreviewImagePath") {
+        match value.as_str() {
+            Some(p) => validate_preview(p, root_dir, &mut problems),
+            None => problems.push(
+                InvalidWorkshopField::WrongType("PreviewImagePath", "a string"),
+            ),
+        }
+    }
+
+    problems
+}
+
+fn validate_tags(value: &Value, problems: &mut Vec<InvalidWorkshopField>) {
+    let tags = match value.as_array() {
+        Some(t) => t,
+        None => {
+            problems.push(InvalidWorkshopField::WrongType("Tags", "a list of strings"));
+            return;
+        }
+    };
+
+    let mut found_tags = HashSet::new();
+
+    for tag in tags {
+        let tag = match tag.as_str() {
+            Some(t) => t,
+            None => {
+                problems.push(InvalidWorkshopField::WrongType("Tags", "a list of strings"));
+                continue;
+            }
+        };
+
+        if tag.trim().is_empty() {
+            problems.push(InvalidWorkshopField::EmptyTag);
+        } else if !RESOURCE_PACK_TAGS.contains(&tag) {
+            problems.push(InvalidWorkshopField::UnknownTag(tag.to_string()));
+        } else if !found_tags.insert(tag) {
+            problems.push(InvalidWorkshopField::DuplicateTag(tag.to_string()));
+        }
+    }
+}
+
+fn validate_visibility(value: &Value, problems: &mut Vec<InvalidWorkshopField>) {
+    let is_valid = match value {
+        Value::Number(n) => n.as_u64().is_some_and(|n| (n as usize) < VISIBILITIES.len()),
+        Value::String(s) => VISIBILITIES.contains(&s.as_str()),
+        _ => false,
+    };
+
+    if !is_valid {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+        };
+
+        problems.push(InvalidWorkshopField::BadVisibility(value));
+    }
+}
+
+fn validate_preview(path_str: &str, root_dir: &PathBuf, problems: &mut Vec<InvalidWorkshopField>) {
+    // Relative paths are resolved from the pack's root.
+    let path = paths::push(root_dir, path_str);
+
+    match fs::metadata(path) {
+        Ok(m) if m.len() > ICON_MAX_BYTES => {
+            problems.push(InvalidWorkshopField::HeavyPreview(path_str.to_string(), m.len()));
+        }
+        Ok(_) => (),
+        Err(_) => problems.push(InvalidWorkshopField::MissingPreview(path_str.to_string())),
+    }
+}
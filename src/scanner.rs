@@ -15,17 +15,6 @@ pub enum ItemStatus<B> {
     Ignored,
 }
 
-impl<B> ItemStatus<B> {
-    /// Returns `true` if the item is an [ItemStatus::Valid].
-    #[inline]
-    pub fn is_valid(&self) -> bool {
-        match self {
-            Self::Valid => true,
-            _ => false,
-        }
-    }
-}
-
 /// Iterates through something, validating each item.
 /// Valid items increase `valid_count` and invalid items are added
 /// to `invalid_items`.
@@ -0,0 +1,166 @@
+//! Data about the game's vanilla sound effects.
+
+use std::{fmt::Display, path::{Path, PathBuf}, str::FromStr};
+
+use crate::wav::WavData;
+
+/// The sample rates sounds are usually recorded at.
+const COMMON_SAMPLE_RATES: [u32; 5] = [11025, 22050, 32000, 44100, 48000];
+
+/// How many times longer than the vanilla sound a pack sound can be before
+/// it is reported.
+const MAX_DURATION_FACTOR: u32 = 4;
+
+/// Error returned in [SoundData]'s implementation of [FromStr].
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseSoundDataError;
+
+/// The format of a sound's samples.
+#[derive(Clone, Copy)]
+pub struct SoundFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub duration_ms: u32,
+}
+
+impl SoundFormat {
+    /// Returns every way `self`, the format of a pack sound, could play badly
+    /// compared to `vanilla`.
+    pub fn compare(&self, vanilla: &SoundFormat, path: &Path) -> Vec<SoundFormatWarning> {
+        let path = path.to_path_buf();
+        let mut warnings = vec![];
+
+        if self.channels > vanilla.channels {
+            warnings.push(SoundFormatWarning::MoreChannels(path.clone(), self.channels, vanilla.channels));
+        }
+
+        if !COMMON_SAMPLE_RATES.contains(&self.sample_rate) {
+            warnings.push(SoundFormatWarning::UnusualSampleRate(path.clone(), self.sample_rate));
+        }
+
+        if vanilla.duration_ms > 0 && self.duration_ms > vanilla.duration_ms * MAX_DURATION_FACTOR {
+            warnings.push(SoundFormatWarning::TooLong(path, self.duration_ms, vanilla.duration_ms));
+        }
+
+        warnings
+    }
+}
+
+impl From<&WavData> for SoundFormat {
+    fn from(wav: &WavData) -> Self {
+        Self {
+            sample_rate: wav.sample_rate,
+            channels: wav.channels,
+            bits_per_sample: wav.bits_per_sample,
+            duration_ms: wav.duration_ms(),
+        }
+    }
+}
+
+/// Data about an individual sound that is relevant to the reference file.
+pub struct SoundData {
+    /// The name of the XNB file.
+    /// The path leading to the parent directory is stored elsewhere.
+    pub file_name: String,
+
+    /// The format of the vanilla sound.
+    /// Missing if it couldn't be read, or in references older than version 1.
+    pub format: Option<SoundFormat>,
+}
+
+impl Display for SoundData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file_name)?;
+
+        if let Some(s) = &self.format {
+            let (rate, channels, bits, duration) =
+                (s.sample_rate, s.channels, s.bits_per_sample, s.duration_ms);
+
+            write!(f, ":{rate}:{channels}:{bits}:{duration}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SoundData {
+    type Err = ParseSoundDataError;
+
+    /// Parses `name`, optionally followed by
+    /// `:sample_rate:channels:bits_per_sample:duration_ms`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (file_name, format) = match s.split_once(':') {
+            Some((n, f)) => (n, Some(f)),
+            None => (s, None),
+        };
+
+        let format = match format {
+            Some(f) => {
+                let values: Vec<u32> = f
+                    .split(':')
+                    .map(|v| v.parse().map_err(|_| ParseSoundDataError))
+                    .collect::<Result<_, _>>()?;
+
+                let [sample_rate, channels, bits_per_sample, duration_ms] = values[..] else {
+                    return Err(ParseSoundDataError);
+                };
+
+                Some(SoundFormat {
+                    sample_rate,
+                    channels: channels.try_into().map_err(|_| ParseSoundDataError)?,
+                    bits_per_sample: bits_per_sample.try_into().map_err(|_| ParseSoundDataError)?,
+                    duration_ms,
+                })
+            }
+            None => None,
+        };
+
+        Ok(Self { file_name: file_name.to_string(), format })
+    }
+}
+
+/// The ways a pack sound could play badly compared to the vanilla one.
+/// These don't make the sound invalid, so they are listed separately.
+pub enum SoundFormatWarning {
+    /// Holds the path and the channel counts of the pack and vanilla sounds.
+    MoreChannels(PathBuf, u16, u16),
+
+    /// Holds the path and the sample rate.
+    UnusualSampleRate(PathBuf, u32),
+
+    /// Holds the path and the durations of the pack and vanilla sounds,
+    /// in milliseconds.
+    TooLong(PathBuf, u32, u32),
+}
+
+impl Display for SoundFormatWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MoreChannels(p, c, v) => write!(
+                f,
+                "{p:?}\t: Has {} but the vanilla sound has {}.",
+                channel_name(*c),
+                channel_name(*v),
+            ),
+            Self::UnusualSampleRate(p, r) => write!(
+                f,
+                "{p:?}\t: Unusual sample rate of {r} Hz. (usually one of {})",
+                COMMON_SAMPLE_RATES.map(|r| r.to_string()).join(", "),
+            ),
+            Self::TooLong(p, d, v) => write!(
+                f,
+                "{p:?}\t: Lasts {d} ms, but the vanilla sound lasts {v} ms. \
+                It may overlap badly with itself.",
+            ),
+        }
+    }
+}
+
+fn channel_name(channels: u16) -> String {
+    match channels {
+        1 => "1 channel (mono)".to_string(),
+        2 => "2 channels (stereo)".to_string(),
+        c => format!("{c} channels"),
+    }
+}
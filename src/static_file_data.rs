@@ -1,19 +1,28 @@
 //! Static data relating to files, such as file names and versions.
 
-use slop_rs::Slop;
-
 /// The type for file versions.
 /// Saved as an alias in case the version numbers become too large.
 pub type Version = u8;
 
-/// The key of the KV in a [Slop] that represents its version.
+/// The key of the KV in a [Slop](slop_rs::Slop) that represents its version.
 pub const VERSION_KEY: &str = "!version";
 
+/// The key of the KV in a reference file that holds how many entries it has.
+pub const COUNT_KEY: &str = "!count";
+
+/// The key of the KV in a reference file that holds the date it was generated
+/// on.
+pub const GENERATED_KEY: &str = "!generated";
+
+/// The key of the KV in a reference file that holds the version of the game
+/// its assets were extracted from.
+pub const GAME_VERSION_KEY: &str = "!game_version";
+
 /// The file name of the image reference file.
 pub const IMAGE_REF_NAME: &str = "images.slop";
 
 /// The version of the image reference file.
-pub const IMAGE_REF_VERSION: Version = 1;
+pub const IMAGE_REF_VERSION: Version = 2;
 
 /// The file name if the CSV file with all the localization entries.
 pub const ALL_LOC_CSV_NAME: &str = "Loc.csv";
@@ -21,34 +30,48 @@ pub const ALL_LOC_CSV_NAME: &str = "Loc.csv";
 /// The file name of the text file with only the translation keys.
 pub const LOC_REF_NAME: &str = "loc_keys.txt";
 
+pub const LOC_REF_VERSION: Version = 1;
+
+/// The file name of the CSV file with the vanilla value of every key,
+/// in each locale.
+pub const LOC_VALUES_REF_NAME: &str = "loc_values.csv";
+
 /// The file name of the text file with the music file names.
 pub const MUSIC_REF_NAME: &str = "music.txt";
 
+pub const MUSIC_REF_VERSION: Version = 1;
+
+/// The file name of the wave bank holding the game's music.
+pub const WAVE_BANK_NAME: &str = "Wave Bank.xwb";
+
 /// The file name of the sound reference file.
 pub const SOUND_REF_NAME: &str = "sounds.slop";
 
-pub const SOUND_REF_VERSION: Version = 0;
+pub const SOUND_REF_VERSION: Version = 1;
+
+/// The file name of the Resource Pack's manifest.
+pub const PACK_FILE_NAME: &str = "pack.json";
+
+/// The file name of the Resource Pack's icon.
+pub const ICON_FILE_NAME: &str = "icon.png";
+
+/// The largest width or height an icon should have.
+pub const ICON_MAX_DIMENSION: usize = 512;
+
+/// The largest file size (in bytes) Steam accepts for preview images.
+pub const ICON_MAX_BYTES: u64 = 1024 * 1024;
+
+/// The file name of the file the game creates when publishing to the Workshop.
+pub const WORKSHOP_FILE_NAME: &str = "workshop.json";
+
+/// The file name of the pack's config file.
+pub const CONFIG_FILE_NAME: &str = "tpack.slop";
+
+/// The file name of the file that lists what `build` should leave out.
+pub const IGNORE_FILE_NAME: &str = ".tpackignore";
+
+/// The signature at the start of every PNG file.
+pub const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
 
 /// The maximum amount of items that can be displayed by lists.
 pub const MAX_LIST_SIZE: usize = 100;
-
-/// Asserts the the [Slop]'s `!version` KV matches the provided version.
-///
-/// ## Panics
-///
-/// Panics if the assertion fails.
-pub fn validate_slop(slop: &Slop, expected_version: Version) {
-    let value = slop
-        .get(VERSION_KEY)
-        .expect("expected slop file to have a `!version` keyvalue");
-
-    let file_version: Version = value
-        .string()
-        .expect("expected the slop file's `!version` keyvalue to be a string kv")
-        .parse()
-        .expect("expected the slop file's `!version` kv to be a positive integer");
-
-    if file_version != expected_version {
-        panic!("expected file version {expected_version}, but got {file_version}");
-    }
-}
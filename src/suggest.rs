@@ -0,0 +1,194 @@
+//! Finds reference names that are similar to invalid ones, so problems can
+//! suggest what was meant.
+
+use std::collections::{HashMap, HashSet};
+
+/// The most suggestions returned for a single name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// An index of names, grouped so similar names can be found without comparing
+/// against every one of them.
+pub struct SuggestionIndex {
+    /// Maps lowercase names to the names themselves.
+    by_lowercase: HashMap<String, Vec<String>>,
+
+    /// Every name, along with its length in chars.
+    names: Vec<(String, usize)>,
+
+    /// Maps each trigram (3 chars in a row) to the indices of the names in
+    /// `names` that hold it.
+    by_trigram: HashMap<[char; 3], Vec<usize>>,
+}
+
+impl SuggestionIndex {
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut by_lowercase: HashMap<String, Vec<String>> = HashMap::new();
+        let mut by_trigram: HashMap<[char; 3], Vec<usize>> = HashMap::new();
+        let mut unique_names = vec![];
+
+        for name in names {
+            let name = name.as_ref();
+            let lowercase = name.to_lowercase();
+
+            if by_lowercase.get(&lowercase).is_some_and(|n| n.iter().any(|n| n == name)) {
+                continue;
+            }
+
+            for trigram in trigrams(name) {
+                by_trigram.entry(trigram).or_default().push(unique_names.len());
+            }
+
+            by_lowercase.entry(lowercase).or_default().push(name.to_string());
+            unique_names.push((name.to_string(), name.chars().count()));
+        }
+
+        Self { by_lowercase, names: unique_names, by_trigram }
+    }
+
+    /// Returns the names that only differ from `name` in letter case.
+    pub fn case_matches(&self, name: &str) -> Vec<String> {
+        match self.by_lowercase.get(&name.to_lowercase()) {
+            Some(n) => n.iter().filter(|n| *n != name).cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the names most similar to `name`, closest first.
+    ///
+    /// Names that only differ in letter case come first, followed by the
+    /// names with the smallest edit distance.
+    pub fn suggest(&self, name: &str) -> Vec<String> {
+        let mut suggestions = self.case_matches(name);
+
+        if suggestions.len() >= MAX_SUGGESTIONS {
+            suggestions.truncate(MAX_SUGGESTIONS);
+            return suggestions;
+        }
+
+        let length = name.chars().count();
+        let max_distance = (length / 5).clamp(1, 4);
+
+        let mut candidates: Vec<(usize, &String)> = self
+            .candidates(name, max_distance)
+            .into_iter()
+            .map(|i| &self.names[i])
+            .filter(|(n, l)| l.abs_diff(length) <= max_distance && !suggestions.contains(n))
+            .map(|(n, _)| (strsim::levenshtein(name, n), n))
+            .filter(|(d, _)| (1..=max_distance).contains(d))
+            .collect();
+
+        candidates.sort();
+
+        suggestions.extend(
+            candidates
+                .into_iter()
+                .take(MAX_SUGGESTIONS - suggestions.len())
+                .map(|(_, n)| n.clone()),
+        );
+
+        suggestions
+    }
+
+    /// Returns the indices of the names that could be at most `max_distance`
+    /// edits away from `name`.
+    ///
+    /// Each edit breaks at most 3 trigrams, so those names must still share
+    /// all but `3 * max_distance` of the trigrams of `name`. Names too short
+    /// for that to rule anything out are all returned.
+    fn candidates(&self, name: &str, max_distance: usize) -> Vec<usize> {
+        let trigrams = trigrams(name);
+        let min_shared = trigrams.len().saturating_sub(3 * max_distance);
+
+        if min_shared == 0 {
+            return (0..self.names.len()).collect();
+        }
+
+        let mut shared = vec![0; self.names.len()];
+
+        for trigram in &trigrams {
+            for i in self.by_trigram.get(trigram).into_iter().flatten() {
+                shared[*i] += 1;
+            }
+        }
+
+        (0..self.names.len()).filter(|i| shared[*i] >= min_shared).collect()
+    }
+}
+
+/// Returns the distinct trigrams of `name`.
+fn trigrams(name: &str) -> HashSet<[char; 3]> {
+    let chars: Vec<char> = name.chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+/// Returns a sentence listing the suggestions, or an empty string if there
+/// are none.
+pub fn did_you_mean(suggestions: &[String]) -> String {
+    let suggestions: Vec<String> = suggestions.iter().map(|s| format!("`{s}`")).collect();
+
+    match suggestions.split_last() {
+        None => String::new(),
+        Some((last, [])) => format!(" Did you mean {last}?"),
+        Some((last, others)) => format!(" Did you mean {} or {last}?", others.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<String> {
+        let words = ["Copper", "Iron", "Gold", "Shortsword", "Pickaxe", "Bow", "Gel", "Wood"];
+        let mut names = vec![];
+
+        for a in words {
+            for b in words {
+                names.push(format!("ItemName.{a}{b}"));
+                names.push(format!("ItemTooltip.{a}{b}"));
+            }
+        }
+
+        names
+    }
+
+    #[test]
+    fn keeps_every_name_a_linear_scan_finds() {
+        let names = names();
+        let index = SuggestionIndex::new(&names);
+
+        for query in ["ItemName.CoperShortsword", "ItemName.GelWod", "Item.Gel", "Gel", "Nothing"] {
+            let max_distance = (query.len() / 5).clamp(1, 4);
+
+            let expected: Vec<&String> = names
+                .iter()
+                .filter(|n| strsim::levenshtein(query, n) <= max_distance)
+                .collect();
+
+            let found: Vec<&String> = index
+                .candidates(query, max_distance)
+                .into_iter()
+                .map(|i| &index.names[i].0)
+                .collect();
+
+            assert!(expected.iter().all(|n| found.contains(n)), "{query}");
+        }
+    }
+
+    #[test]
+    fn suggests_case_matches_first() {
+        let index = SuggestionIndex::new(["ItemName.Gel", "ItemName.GEL", "ItemName.Gem"]);
+        assert_eq!(index.suggest("ItemName.gel"), ["ItemName.Gel", "ItemName.GEL", "ItemName.Gem"]);
+    }
+
+    #[test]
+    fn suggests_nothing_for_exact_or_distant_names() {
+        let index = SuggestionIndex::new(["ItemName.Gel"]);
+
+        assert!(index.suggest("ItemName.Gel").is_empty());
+        assert!(index.suggest("NPCName.Guide").is_empty());
+    }
+}
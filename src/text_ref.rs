@@ -0,0 +1,144 @@
+//! Reads and writes the plain text reference files, which hold one entry per
+//! line after a header of `!key = value` lines.
+
+use std::{
+    fs,
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    output,
+    refs::RefFile,
+    static_file_data::{Version, COUNT_KEY, GAME_VERSION_KEY, GENERATED_KEY, VERSION_KEY},
+};
+
+/// The contents of a text reference file.
+pub struct TextRef {
+    /// The `!key = value` lines at the start of the file, keyed with the `!`,
+    /// in the order they are written in.
+    pub header: Vec<(String, String)>,
+
+    /// Every non-empty line after the header.
+    pub lines: Vec<String>,
+}
+
+impl TextRef {
+    /// Reads the text reference at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let mut header = vec![];
+        let mut lines = vec![];
+
+        for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            match line.split_once('=') {
+                Some((k, v)) if lines.is_empty() && k.starts_with('!') => {
+                    header.push((k.trim().to_string(), v.trim().to_string()));
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+
+        Ok(Self { header, lines })
+    }
+
+    /// Creates a reference holding `lines`, with a header holding their
+    /// `version`, count, the date they were generated on and the version of the
+    /// game they were extracted from, if it is known.
+    pub fn new(version: Version, lines: Vec<String>, game_version: Option<&str>) -> Self {
+        let mut header = vec![
+            (VERSION_KEY.to_string(), version.to_string()),
+            (COUNT_KEY.to_string(), lines.len().to_string()),
+            (GENERATED_KEY.to_string(), today()),
+        ];
+
+        if let Some(v) = game_version {
+            header.push((GAME_VERSION_KEY.to_string(), v.to_string()));
+        }
+
+        Self { header, lines }
+    }
+
+    /// Writes the reference to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut contents = String::new();
+
+        for (key, value) in &self.header {
+            contents.push_str(&format!("{key} = {value}\n"));
+        }
+
+        for line in &self.lines {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+
+        fs::write(path, contents)
+    }
+
+    /// Returns the value of `key` in the header.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.header.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the version in the header.
+    /// Files written before text references were versioned count as version 0.
+    pub fn version(&self) -> Version {
+        match self.get(VERSION_KEY) {
+            Some(v) => v
+                .parse()
+                .expect("expected the text reference's `!version` to be a positive integer"),
+            None => 0,
+        }
+    }
+
+    /// Checks the version of the reference, and warns if it doesn't hold as
+    /// many lines as its `!count` says, which means it was edited.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the reference can't be read by this version of the tool.
+    pub fn validate(&self, file: &RefFile) {
+        let name = file.name;
+        file.check_version(self.version());
+
+        // Version 0 files have no count to check.
+        let Some(count) = self.get(COUNT_KEY) else {
+            return;
+        };
+
+        let count: usize = count
+            .parse()
+            .unwrap_or_else(|_| panic!("expected `{name}`'s `!count` to be a positive integer"));
+
+        if count != self.lines.len() {
+            output::warn(&format!(
+                "`{name}` should hold {count} entries, but it holds {}. \
+                It was likely edited or truncated; run `gen` again.",
+                self.lines.len(),
+            ));
+        }
+    }
+}
+
+/// Returns today's date, in UTC, as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86400)
+        .unwrap_or(0) as i64;
+
+    // Converts the days since 1970-01-01 into a civil date, splitting time into
+    // 400 year eras that start on March 1st.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -0,0 +1,185 @@
+//! Reads the format and samples of WAV files.
+
+use std::fmt::Display;
+
+/// The format tag of uncompressed PCM samples.
+pub const FORMAT_PCM: u16 = 1;
+
+/// The size of the `fmt ` chunk of PCM files, without the extra size field.
+const PCM_FORMAT_SIZE: usize = 16;
+
+/// The possible problems with a WAV file.
+pub enum InvalidWav {
+    NotWav,
+    Truncated,
+    MissingFormat,
+    MissingData,
+
+    /// Holds the format tag.
+    NotPcm(u16),
+
+    /// Holds the bit depth.
+    BadBitDepth(u16),
+}
+
+impl Display for InvalidWav {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotWav => write!(f, "This is not a WAV file."),
+            Self::Truncated => write!(f, "The file ends in the middle of a chunk. (truncated)"),
+            Self::MissingFormat => write!(f, "The file has no valid `fmt ` chunk."),
+            Self::MissingData => write!(f, "The file has no `data` chunk."),
+            Self::NotPcm(t) => write!(f, "The samples are compressed (format {t}), not PCM."),
+            Self::BadBitDepth(b) => write!(f, "Uses {b} bits per sample. (expected 8 or 16)"),
+        }
+    }
+}
+
+/// The contents of a PCM WAV file.
+pub struct WavData {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+
+    /// The size of a single frame (a sample for every channel), in bytes.
+    pub block_align: u16,
+
+    /// The raw samples.
+    pub samples: Vec<u8>,
+}
+
+impl WavData {
+    /// Parses the WAV file in `bytes`.
+    ///
+    /// Only 8 and 16 bit PCM files are accepted, as those are the only ones
+    /// XNA can play.
+    pub fn parse(bytes: &[u8]) -> Result<Self, InvalidWav> {
+        if !bytes.starts_with(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+            return Err(InvalidWav::NotWav);
+        }
+
+        let mut format = None;
+        let mut samples = None;
+        let mut offset = 12;
+
+        while offset + 8 <= bytes.len() {
+            let chunk_type = &bytes[offset..offset + 4];
+            // SAFETY: The slice is exactly 4 bytes long.
+            let length = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap());
+            let start = offset + 8;
+            let chunk = bytes.get(start..start + length as usize).ok_or(InvalidWav::Truncated)?;
+
+            match chunk_type {
+                b"fmt " => format = Some(chunk),
+                b"data" => samples = Some(chunk),
+                _ => (),
+            }
+
+            // Chunks are padded to an even size.
+            offset = start + length as usize + (length as usize & 1);
+        }
+
+        let format = format
+            .filter(|f| f.len() >= PCM_FORMAT_SIZE)
+            .ok_or(InvalidWav::MissingFormat)?;
+
+        let samples = samples.ok_or(InvalidWav::MissingData)?;
+
+        let read_u16 = |i: usize| u16::from_le_bytes([format[i], format[i + 1]]);
+        let read_u32 = |i: usize| u32::from_le_bytes([format[i], format[i + 1], format[i + 2], format[i + 3]]);
+
+        let format_tag = read_u16(0);
+
+        if format_tag != FORMAT_PCM {
+            return Err(InvalidWav::NotPcm(format_tag));
+        }
+
+        let bits_per_sample = read_u16(14);
+
+        if bits_per_sample != 8 && bits_per_sample != 16 {
+            return Err(InvalidWav::BadBitDepth(bits_per_sample));
+        }
+
+        Ok(Self {
+            channels: read_u16(2),
+            sample_rate: read_u32(4),
+            bits_per_sample,
+            block_align: read_u16(12),
+            samples: samples.to_vec(),
+        })
+    }
+
+    /// The amount of frames (a sample for every channel) in the file.
+    pub fn frame_count(&self) -> u32 {
+        match self.block_align {
+            0 => 0,
+            b => (self.samples.len() / b as usize) as u32,
+        }
+    }
+
+    /// The duration of the sound, in milliseconds.
+    pub fn duration_ms(&self) -> u32 {
+        match self.sample_rate {
+            0 => 0,
+            r => (self.frame_count() as u64 * 1000 / r as u64) as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A WAV file with the given format tag and bit depth, holding `samples`.
+    fn wav(format_tag: u16, bits_per_sample: u16, samples: &[u8]) -> Vec<u8> {
+        let block_align = 2 * bits_per_sample / 8;
+        let mut format = vec![];
+        format.extend(format_tag.to_le_bytes());
+        format.extend(2u16.to_le_bytes());
+        format.extend(22050u32.to_le_bytes());
+        format.extend((22050 * block_align as u32).to_le_bytes());
+        format.extend(block_align.to_le_bytes());
+        format.extend(bits_per_sample.to_le_bytes());
+
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+
+        for (chunk_type, chunk) in [(b"fmt ", &format[..]), (b"LIST", b"odd"), (b"data", samples)] {
+            bytes.extend(chunk_type);
+            bytes.extend((chunk.len() as u32).to_le_bytes());
+            bytes.extend(chunk);
+
+            if chunk.len() % 2 == 1 {
+                bytes.push(0);
+            }
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn reads_pcm_files() {
+        let wav = WavData::parse(&wav(FORMAT_PCM, 16, &[0; 4 * 2205])).ok().unwrap();
+
+        assert_eq!((wav.channels, wav.sample_rate, wav.bits_per_sample), (2, 22050, 16));
+        assert_eq!(wav.frame_count(), 2205);
+        assert_eq!(wav.duration_ms(), 100);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = wav(FORMAT_PCM, 16, &[0; 16]);
+        assert!(matches!(WavData::parse(&bytes[..bytes.len() - 1]), Err(InvalidWav::Truncated)));
+        assert!(matches!(WavData::parse(&bytes[..46]), Err(InvalidWav::Truncated)));
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        assert!(matches!(WavData::parse(b"RIFF\0\0\0\0AVI "), Err(InvalidWav::NotWav)));
+        assert!(matches!(WavData::parse(b"RIFF\0\0\0\0WAVE"), Err(InvalidWav::MissingFormat)));
+        assert!(matches!(WavData::parse(&wav(2, 4, &[])), Err(InvalidWav::NotPcm(2))));
+        assert!(matches!(
+            WavData::parse(&wav(FORMAT_PCM, 24, &[])),
+            Err(InvalidWav::BadBitDepth(24)),
+        ));
+    }
+}
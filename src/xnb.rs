@@ -0,0 +1,395 @@
+//! Reads the headers of XNB files, the containers XNA uses for its assets,
+//! and writes sound effect ones.
+
+use std::fmt::Display;
+
+use crate::{sound_data::SoundFormat, wav::{WavData, FORMAT_PCM}};
+
+/// The magic bytes every XNB file starts with.
+pub const XNB_MAGIC: &[u8; 3] = b"XNB";
+
+/// The format version used by XNA 4.0, which the game is built on.
+pub const XNB_VERSION: u8 = 5;
+
+/// The name of the type reader of sound effects.
+pub const SOUND_EFFECT_READER: &str = "Microsoft.Xna.Framework.Content.SoundEffectReader";
+
+/// The platforms XNB files can target, by their identifier.
+const PLATFORMS: [(u8, &str); 3] = [(b'w', "Windows"), (b'm', "Windows Phone"), (b'x', "Xbox 360")];
+
+/// The size of the `WAVEFORMATEX` struct that sound effects store their format
+/// in.
+const WAVE_FORMAT_SIZE: u32 = 18;
+
+/// The flag set on XNB files compressed with LZX.
+const FLAG_COMPRESSED_LZX: u8 = 0x80;
+
+/// The flag set on XNB files compressed with LZ4, which MonoGame added.
+/// XNA can't read them.
+const FLAG_COMPRESSED_LZ4: u8 = 0x40;
+
+/// The size of the header, up to and including the file size.
+const HEADER_SIZE: usize = 10;
+
+/// The possible problems with the header of an XNB file.
+pub enum InvalidXnb {
+    /// Holds the name of the format the file is really in, if it is known.
+    NotXnb(Option<&'static str>),
+    Truncated,
+
+    /// Holds the platform identifier.
+    BadPlatform(u8),
+
+    /// Holds the format version.
+    BadVersion(u8),
+
+    /// Holds the declared file size and the real one.
+    BadFileSize(u32, usize),
+    CompressedLz4,
+    NoTypeReaders,
+
+    /// Holds the name of the primary type reader and the expected one.
+    WrongReader(String, &'static str),
+}
+
+impl Display for InvalidXnb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotXnb(Some(n)) => write!(f, "This is a {n} file with an `.xnb` extension."),
+            Self::NotXnb(None) => write!(f, "This is not an XNB file."),
+            Self::Truncated => write!(f, "The file ends in the middle of its header. (truncated)"),
+            Self::BadPlatform(p) => write!(
+                f,
+                "Unknown target platform `{}`. (expected one of: {})",
+                p.escape_ascii(),
+                PLATFORMS.map(|(_, n)| n).join(", "),
+            ),
+            Self::BadVersion(v) => {
+                write!(f, "Unsupported format version {v}. (expected {XNB_VERSION})")
+            }
+            Self::BadFileSize(d, r) => write!(
+                f,
+                "The header says the file takes {d} bytes, but it takes {r}. (truncated or padded)",
+            ),
+            Self::CompressedLz4 => write!(
+                f,
+                "The file is compressed with LZ4, which XNA can't read. (only LZX is supported)",
+            ),
+            Self::NoTypeReaders => write!(f, "The file declares no type readers."),
+            Self::WrongReader(r, e) => write!(
+                f,
+                "Holds a `{}`, but `{}` was expected.",
+                short_reader_name(r),
+                short_reader_name(e),
+            ),
+        }
+    }
+}
+
+/// The header of an XNB file.
+pub struct XnbHeader {
+    /// The name of the first type reader, which reads the primary asset.
+    /// [None] if the file is compressed, as the readers are compressed too.
+    pub primary_reader: Option<String>,
+
+    /// Where the data of the primary asset starts.
+    /// [None] if the file is compressed.
+    primary_offset: Option<usize>,
+}
+
+impl XnbHeader {
+    /// Parses the header of the XNB file in `bytes`, checking it against the
+    /// file's real size.
+    pub fn parse(bytes: &[u8]) -> Result<Self, InvalidXnb> {
+        if !bytes.starts_with(XNB_MAGIC) {
+            return Err(InvalidXnb::NotXnb(sniff_format(bytes)));
+        }
+
+        let header = bytes.get(..HEADER_SIZE).ok_or(InvalidXnb::Truncated)?;
+        let (platform, version, flags) = (header[3], header[4], header[5]);
+        // SAFETY: The slice is exactly 4 bytes long.
+        let file_size = u32::from_le_bytes(header[6..10].try_into().unwrap());
+
+        if !PLATFORMS.iter().any(|(p, _)| *p == platform) {
+            return Err(InvalidXnb::BadPlatform(platform));
+        }
+
+        if version != XNB_VERSION {
+            return Err(InvalidXnb::BadVersion(version));
+        }
+
+        if file_size as usize != bytes.len() {
+            return Err(InvalidXnb::BadFileSize(file_size, bytes.len()));
+        }
+
+        if flags & FLAG_COMPRESSED_LZ4 != 0 {
+            return Err(InvalidXnb::CompressedLz4);
+        }
+
+        if flags & FLAG_COMPRESSED_LZX != 0 {
+            return Ok(Self { primary_reader: None, primary_offset: None });
+        }
+
+        let mut offset = HEADER_SIZE;
+        let reader_count = read_7bit_int(bytes, &mut offset)?;
+
+        if reader_count == 0 {
+            return Err(InvalidXnb::NoTypeReaders);
+        }
+
+        let mut readers = vec![];
+
+        for _ in 0..reader_count {
+            readers.push(read_string(bytes, &mut offset)?);
+            // Skips the version of the reader.
+            offset += 4;
+        }
+
+        // Skips the shared resource count and the type id of the primary asset.
+        read_7bit_int(bytes, &mut offset)?;
+        read_7bit_int(bytes, &mut offset)?;
+
+        Ok(Self {
+            primary_reader: readers.into_iter().next(),
+            primary_offset: Some(offset),
+        })
+    }
+
+    /// Reads the format of the sound effect in `bytes`, the file the header
+    /// was parsed from.
+    /// Returns [None] if the file is compressed or the format can't be read.
+    pub fn sound_effect_format(&self, bytes: &[u8]) -> Option<SoundFormat> {
+        let offset = self.primary_offset?;
+        let read_u16 = |i: usize| Some(u16::from_le_bytes(bytes.get(i..i + 2)?.try_into().ok()?));
+        let read_u32 = |i: usize| Some(u32::from_le_bytes(bytes.get(i..i + 4)?.try_into().ok()?));
+
+        let format_size = read_u32(offset)? as usize;
+        let format = offset + 4;
+
+        let channels = read_u16(format + 2)?;
+        let sample_rate = read_u32(format + 4)?;
+        let block_align = read_u16(format + 12)?;
+        let bits_per_sample = read_u16(format + 14)?;
+        let data_size = read_u32(format + format_size)?;
+
+        let duration_ms = match (block_align, sample_rate) {
+            (0, _) | (_, 0) => 0,
+            (b, r) => (data_size as u64 / b as u64 * 1000 / r as u64) as u32,
+        };
+
+        Some(SoundFormat { sample_rate, channels, bits_per_sample, duration_ms })
+    }
+
+    /// Checks that the primary type reader is `expected`.
+    /// Compressed files always pass, as their readers can't be seen.
+    pub fn expect_reader(&self, expected: &'static str) -> Result<(), InvalidXnb> {
+        match &self.primary_reader {
+            Some(r) if short_reader_name(r) != short_reader_name(expected) => {
+                Err(InvalidXnb::WrongReader(r.clone(), expected))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Wraps the samples of a WAV file into an uncompressed SoundEffect XNB file
+/// for Windows.
+pub fn write_sound_effect(wav: &WavData) -> Vec<u8> {
+    let mut content = vec![];
+
+    // Type readers.
+    write_7bit_int(&mut content, 1);
+    write_string(&mut content, SOUND_EFFECT_READER);
+    content.extend(0i32.to_le_bytes());
+
+    // Shared resources, followed by the type reader of the primary asset.
+    write_7bit_int(&mut content, 0);
+    write_7bit_int(&mut content, 1);
+
+    // The `WAVEFORMATEX` struct.
+    let byte_rate = wav.sample_rate * wav.block_align as u32;
+    content.extend(WAVE_FORMAT_SIZE.to_le_bytes());
+    content.extend(FORMAT_PCM.to_le_bytes());
+    content.extend(wav.channels.to_le_bytes());
+    content.extend(wav.sample_rate.to_le_bytes());
+    content.extend(byte_rate.to_le_bytes());
+    content.extend(wav.block_align.to_le_bytes());
+    content.extend(wav.bits_per_sample.to_le_bytes());
+    content.extend(0u16.to_le_bytes());
+
+    content.extend((wav.samples.len() as u32).to_le_bytes());
+    content.extend(&wav.samples);
+
+    // Loop start, loop length and duration.
+    content.extend(0u32.to_le_bytes());
+    content.extend(wav.frame_count().to_le_bytes());
+    content.extend(wav.duration_ms().to_le_bytes());
+
+    let file_size = (HEADER_SIZE + content.len()) as u32;
+
+    let mut bytes = Vec::with_capacity(file_size as usize);
+    bytes.extend(XNB_MAGIC);
+    bytes.extend([PLATFORMS[0].0, XNB_VERSION, 0]);
+    bytes.extend(file_size.to_le_bytes());
+    bytes.extend(content);
+    bytes
+}
+
+/// Returns the name of a type reader without its assembly information
+/// (everything after the first comma).
+fn short_reader_name(name: &str) -> &str {
+    name.split(',').next().unwrap_or(name).trim()
+}
+
+/// Reads an integer stored in the 7-bit encoded format .NET uses.
+fn read_7bit_int(bytes: &[u8], offset: &mut usize) -> Result<u32, InvalidXnb> {
+    let mut result = 0;
+
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.get(*offset).ok_or(InvalidXnb::Truncated)?;
+        *offset += 1;
+        result |= ((byte & 0x7F) as u32) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+
+    Err(InvalidXnb::Truncated)
+}
+
+/// Writes an integer in the 7-bit encoded format .NET uses.
+fn write_7bit_int(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+/// Writes a string prefixed by its length, as .NET's `BinaryWriter` does.
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    write_7bit_int(bytes, string.len() as u32);
+    bytes.extend(string.as_bytes());
+}
+
+/// Reads a string prefixed by its length, as .NET's `BinaryWriter` writes it.
+fn read_string(bytes: &[u8], offset: &mut usize) -> Result<String, InvalidXnb> {
+    let length = read_7bit_int(bytes, offset)? as usize;
+    let string = bytes.get(*offset..*offset + length).ok_or(InvalidXnb::Truncated)?;
+    *offset += length;
+
+    Ok(String::from_utf8_lossy(string).to_string())
+}
+
+/// Guesses the format of a file that isn't an XNB from its first bytes.
+fn sniff_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WAVE") {
+        Some("WAV")
+    } else if bytes.starts_with(b"OggS") {
+        Some("Ogg")
+    } else if bytes.starts_with(b"ID3") || bytes.starts_with(&[0xFF, 0xFB]) {
+        Some("MP3")
+    } else if bytes.starts_with(b"\x89PNG") {
+        Some("PNG")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An uncompressed XNB file holding a single asset read by `reader`.
+    fn xnb(flags: u8, reader: &str) -> Vec<u8> {
+        let mut content = vec![];
+        write_7bit_int(&mut content, 1);
+        write_string(&mut content, reader);
+        content.extend(0i32.to_le_bytes());
+        write_7bit_int(&mut content, 0);
+        write_7bit_int(&mut content, 1);
+
+        let mut bytes = XNB_MAGIC.to_vec();
+        bytes.extend([b'w', XNB_VERSION, flags]);
+        bytes.extend(((HEADER_SIZE + content.len()) as u32).to_le_bytes());
+        bytes.extend(content);
+        bytes
+    }
+
+    #[test]
+    fn reads_the_primary_reader() {
+        let reader = format!("{SOUND_EFFECT_READER}, Microsoft.Xna.Framework, Version=4.0.0.0");
+        let header = XnbHeader::parse(&xnb(0, &reader)).ok().unwrap();
+
+        assert_eq!(header.primary_reader.as_deref(), Some(reader.as_str()));
+        assert!(header.expect_reader(SOUND_EFFECT_READER).is_ok());
+        assert!(matches!(
+            header.expect_reader("Microsoft.Xna.Framework.Content.Texture2DReader"),
+            Err(InvalidXnb::WrongReader(..)),
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = xnb(0, SOUND_EFFECT_READER);
+
+        assert!(matches!(XnbHeader::parse(&bytes[..6]), Err(InvalidXnb::Truncated)));
+        assert!(matches!(
+            XnbHeader::parse(&bytes[..bytes.len() - 1]),
+            Err(InvalidXnb::BadFileSize(..)),
+        ));
+    }
+
+    #[test]
+    fn rejects_corrupt_headers() {
+        let mut bytes = xnb(0, SOUND_EFFECT_READER);
+        bytes[3] = b'?';
+        assert!(matches!(XnbHeader::parse(&bytes), Err(InvalidXnb::BadPlatform(b'?'))));
+
+        let mut bytes = xnb(0, SOUND_EFFECT_READER);
+        bytes[4] = 4;
+        assert!(matches!(XnbHeader::parse(&bytes), Err(InvalidXnb::BadVersion(4))));
+
+        // A reader name that claims to be longer than the file.
+        let mut bytes = xnb(0, SOUND_EFFECT_READER);
+        bytes[HEADER_SIZE + 1] = 0x7F;
+        assert!(matches!(XnbHeader::parse(&bytes), Err(InvalidXnb::Truncated)));
+
+        assert!(matches!(XnbHeader::parse(b"OggS"), Err(InvalidXnb::NotXnb(Some("Ogg")))));
+    }
+
+    #[test]
+    fn reads_back_written_sound_effects() {
+        let wav = WavData {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            block_align: 4,
+            samples: vec![0; 4 * 4410],
+        };
+
+        let bytes = write_sound_effect(&wav);
+        let header = XnbHeader::parse(&bytes).ok().unwrap();
+        assert!(header.expect_reader(SOUND_EFFECT_READER).is_ok());
+
+        let format = header.sound_effect_format(&bytes).unwrap();
+        assert_eq!(
+            (format.sample_rate, format.channels, format.bits_per_sample, format.duration_ms),
+            (44100, 2, 16, 100),
+        );
+    }
+
+    #[test]
+    fn recognizes_compressed_files() {
+        let bytes = xnb(FLAG_COMPRESSED_LZX, SOUND_EFFECT_READER);
+        let header = XnbHeader::parse(&bytes).ok().unwrap();
+        assert!(header.primary_reader.is_none());
+
+        assert!(matches!(
+            XnbHeader::parse(&xnb(FLAG_COMPRESSED_LZ4, SOUND_EFFECT_READER)),
+            Err(InvalidXnb::CompressedLz4),
+        ));
+    }
+}
@@ -0,0 +1,324 @@
+//! Reads the entries of XACT wave banks, which is where the game keeps its
+//! music.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+/// The magic bytes of little-endian wave banks.
+const XWB_MAGIC: &[u8; 4] = b"WBND";
+
+/// The magic bytes of big-endian (Xbox 360) wave banks.
+const XWB_MAGIC_BE: &[u8; 4] = b"DNBW";
+
+/// The oldest XACT content version whose layout is supported.
+const MIN_CONTENT_VERSION: u32 = 42;
+
+/// The amount of segments listed in the header.
+const SEGMENT_COUNT: usize = 5;
+
+/// The size of the header: magic, versions and segments.
+const HEADER_SIZE: usize = 12 + SEGMENT_COUNT * 8;
+
+/// The size of the bank data segment, up to the compact format field.
+const BANK_DATA_SIZE: usize = 4 * 2 + 64 + 4 * 4;
+
+/// The index of the segment with the entry metadata.
+const SEGMENT_ENTRY_METADATA: usize = 1;
+
+/// The index of the segment with the entry names.
+const SEGMENT_ENTRY_NAMES: usize = 3;
+
+/// The bank flag set when the entries have names.
+const FLAG_ENTRY_NAMES: u32 = 0x0001_0000;
+
+/// The bank flag set when the entries use the compact format.
+const FLAG_COMPACT: u32 = 0x0002_0000;
+
+/// The codecs an entry can be stored in, by their id.
+const CODECS: [&str; 4] = ["PCM", "XMA", "ADPCM", "WMA"];
+
+/// The possible problems with a wave bank.
+#[derive(Debug)]
+pub enum InvalidWaveBank {
+    NotWaveBank,
+    Truncated,
+    BigEndian,
+    CompactEntries,
+
+    /// Holds the name of the segment whose entries don't fit in it.
+    BadSegment(&'static str),
+
+    /// Holds the content version.
+    OldVersion(u32),
+}
+
+impl Display for InvalidWaveBank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotWaveBank => write!(f, "This is not an XACT wave bank."),
+            Self::Truncated => write!(f, "The file ends in the middle of its metadata. (truncated)"),
+            Self::BigEndian => write!(f, "Xbox 360 wave banks are not supported."),
+            Self::CompactEntries => write!(f, "Wave banks with compact entries are not supported."),
+            Self::BadSegment(s) => write!(f, "The entries don't fit in the {s} segment. (corrupt)"),
+            Self::OldVersion(v) => write!(
+                f,
+                "Unsupported XACT content version {v}. (expected {MIN_CONTENT_VERSION} or later)",
+            ),
+        }
+    }
+}
+
+impl Error for InvalidWaveBank {}
+
+/// An entry of a wave bank.
+pub struct WaveBankEntry {
+    /// The name of the entry. Wave banks often don't store names.
+    pub name: Option<String>,
+    pub codec: &'static str,
+    pub channels: u32,
+    pub sample_rate: u32,
+    pub duration_ms: u64,
+}
+
+impl Display for WaveBankEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let channels = match self.channels {
+            1 => "mono".to_string(),
+            2 => "stereo".to_string(),
+            c => format!("{c} channels"),
+        };
+
+        let seconds = self.duration_ms / 1000;
+
+        write!(
+            f,
+            "{}, {} Hz, {channels}, {}:{:02}",
+            self.codec,
+            self.sample_rate,
+            seconds / 60,
+            seconds % 60,
+        )
+    }
+}
+
+/// Reads the entries of the wave bank at `path`.
+///
+/// Only the header and metadata are read, as wave banks can be very large.
+pub fn read_entries(path: &PathBuf) -> Result<Vec<WaveBankEntry>, Box<dyn Error>> {
+    read_bank(&mut File::open(path)?)
+}
+
+fn read_bank<R: Read + Seek>(file: &mut R) -> Result<Vec<WaveBankEntry>, Box<dyn Error>> {
+    let header = read_at(file, 0, HEADER_SIZE)?;
+
+    match &header[..4] {
+        m if m == XWB_MAGIC => (),
+        m if m == XWB_MAGIC_BE => return Err(Box::new(InvalidWaveBank::BigEndian)),
+        _ => return Err(Box::new(InvalidWaveBank::NotWaveBank)),
+    }
+
+    let content_version = read_u32(&header, 4);
+
+    if content_version < MIN_CONTENT_VERSION {
+        return Err(Box::new(InvalidWaveBank::OldVersion(content_version)));
+    }
+
+    let segments: Vec<(u64, usize)> = (0..SEGMENT_COUNT)
+        .map(|i| (read_u32(&header, 12 + i * 8) as u64, read_u32(&header, 16 + i * 8) as usize))
+        .collect();
+
+    let bank_data = read_at(file, segments[0].0, BANK_DATA_SIZE)?;
+    let flags = read_u32(&bank_data, 0);
+    let entry_count = read_u32(&bank_data, 4) as usize;
+    let metadata_size = read_u32(&bank_data, 72) as usize;
+    let name_size = read_u32(&bank_data, 76) as usize;
+
+    if metadata_size < 8 {
+        return Err(Box::new(InvalidWaveBank::NotWaveBank));
+    }
+
+    if flags & FLAG_COMPACT != 0 {
+        return Err(Box::new(InvalidWaveBank::CompactEntries));
+    }
+
+    // The sizes are checked against the segments before anything is read, so
+    // a corrupt entry count can't make it allocate gigabytes.
+    let (metadata_offset, metadata_length) = segments[SEGMENT_ENTRY_METADATA];
+    let metadata_length = entry_count
+        .checked_mul(metadata_size)
+        .filter(|l| *l <= metadata_length)
+        .ok_or(InvalidWaveBank::BadSegment("entry metadata"))?;
+
+    let metadata = read_at(file, metadata_offset, metadata_length)?;
+
+    let names = match segments[SEGMENT_ENTRY_NAMES] {
+        (o, l) if flags & FLAG_ENTRY_NAMES != 0 && l > 0 && name_size > 0 => {
+            let names_length = entry_count
+                .checked_mul(name_size)
+                .filter(|n| *n <= l)
+                .ok_or(InvalidWaveBank::BadSegment("entry name"))?;
+
+            Some(read_at(file, o, names_length)?)
+        }
+        _ => None,
+    };
+
+    let entries = (0..entry_count)
+        .map(|i| {
+            let entry = &metadata[i * metadata_size..];
+            let duration = read_u32(entry, 0) >> 4;
+            let format = read_u32(entry, 4);
+
+            let sample_rate = (format >> 5) & 0x3FFFF;
+
+            let name = names.as_ref().map(|n| {
+                let name = &n[i * name_size..(i + 1) * name_size];
+                let end = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+                String::from_utf8_lossy(&name[..end]).trim().to_string()
+            });
+
+            WaveBankEntry {
+                name: name.filter(|n| !n.is_empty()),
+                codec: CODECS[(format & 0b11) as usize],
+                channels: (format >> 2) & 0b111,
+                sample_rate,
+                duration_ms: match sample_rate {
+                    0 => 0,
+                    r => duration as u64 * 1000 / r as u64,
+                },
+            }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Reads `length` bytes starting at `offset`.
+fn read_at<R: Read + Seek>(file: &mut R, offset: u64, length: usize)
+    -> Result<Vec<u8>, Box<dyn Error>>
+{
+    let mut buffer = vec![];
+    file.seek(SeekFrom::Start(offset))?;
+    file.take(length as u64).read_to_end(&mut buffer)?;
+
+    match buffer.len() == length {
+        true => Ok(buffer),
+        false => Err(Box::new(InvalidWaveBank::Truncated)),
+    }
+}
+
+/// Reads a little-endian [u32] at `offset`.
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    // SAFETY: The slice is exactly 4 bytes long.
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// The size of each entry's metadata in [bank].
+    const METADATA_SIZE: usize = 24;
+
+    /// The size of each entry's name in [bank].
+    const NAME_SIZE: usize = 64;
+
+    /// A wave bank holding a 1 second stereo PCM entry named `Music_1` and a 2
+    /// second mono ADPCM one named `Music_2`.
+    fn bank() -> Vec<u8> {
+        let bank_data_offset = HEADER_SIZE;
+        let metadata_offset = bank_data_offset + BANK_DATA_SIZE;
+        let names_offset = metadata_offset + 2 * METADATA_SIZE;
+
+        let mut bytes = XWB_MAGIC.to_vec();
+        bytes.extend(46u32.to_le_bytes());
+        bytes.extend(44u32.to_le_bytes());
+
+        let segments = [
+            (bank_data_offset, BANK_DATA_SIZE),
+            (metadata_offset, 2 * METADATA_SIZE),
+            (0, 0),
+            (names_offset, 2 * NAME_SIZE),
+            (0, 0),
+        ];
+
+        for (offset, length) in segments {
+            bytes.extend((offset as u32).to_le_bytes());
+            bytes.extend((length as u32).to_le_bytes());
+        }
+
+        bytes.extend(FLAG_ENTRY_NAMES.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend([0; 64]);
+        bytes.extend((METADATA_SIZE as u32).to_le_bytes());
+        bytes.extend((NAME_SIZE as u32).to_le_bytes());
+        bytes.extend([0; 8]);
+
+        // Codec, channels and sample rate, packed into the format field.
+        for (codec, channels, sample_rate) in [(0u32, 2u32, 44100u32), (2, 1, 22050)] {
+            bytes.extend(((sample_rate * (1 + codec / 2)) << 4).to_le_bytes());
+            bytes.extend((codec | channels << 2 | sample_rate << 5).to_le_bytes());
+            bytes.extend([0; METADATA_SIZE - 8]);
+        }
+
+        for name in ["Music_1", "Music_2"] {
+            let mut name = name.as_bytes().to_vec();
+            name.resize(NAME_SIZE, 0);
+            bytes.extend(name);
+        }
+
+        bytes
+    }
+
+    fn read(bytes: Vec<u8>) -> Result<Vec<WaveBankEntry>, InvalidWaveBank> {
+        read_bank(&mut Cursor::new(bytes)).map_err(|e| match e.downcast::<InvalidWaveBank>() {
+            Ok(e) => *e,
+            Err(e) => panic!("{e}"),
+        })
+    }
+
+    #[test]
+    fn reads_entries() {
+        let entries = read(bank()).unwrap();
+        let summary: Vec<(Option<&str>, String)> = entries
+            .iter()
+            .map(|e| (e.name.as_deref(), e.to_string()))
+            .collect();
+
+        assert_eq!(summary, [
+            (Some("Music_1"), "PCM, 44100 Hz, stereo, 0:01".to_string()),
+            (Some("Music_2"), "ADPCM, 22050 Hz, mono, 0:02".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn rejects_truncated_banks() {
+        let mut bytes = bank();
+        bytes.truncate(bytes.len() - 1);
+        assert!(matches!(read(bytes), Err(InvalidWaveBank::Truncated)));
+
+        assert!(matches!(read(bank()[..20].to_vec()), Err(InvalidWaveBank::Truncated)));
+    }
+
+    #[test]
+    fn rejects_corrupt_banks() {
+        assert!(matches!(read(b"RIFF".repeat(20)), Err(InvalidWaveBank::NotWaveBank)));
+
+        // An entry count that would overflow the size of the metadata.
+        let mut bytes = bank();
+        bytes[HEADER_SIZE + 4..HEADER_SIZE + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(read(bytes), Err(InvalidWaveBank::BadSegment(_))));
+
+        // A name size larger than the name segment.
+        let mut bytes = bank();
+        bytes[HEADER_SIZE + 76..HEADER_SIZE + 80].copy_from_slice(&128u32.to_le_bytes());
+        assert!(matches!(read(bytes), Err(InvalidWaveBank::BadSegment("entry name"))));
+    }
+}